@@ -0,0 +1,75 @@
+//! Generates the subset of the bundled font embedded when the `full-font`
+//! feature is disabled.
+//!
+//! The characters are picked by the `latin`, `latin-ext`, `greek`, `cyrillic`
+//! and `cjk` features, plus any character listed in the
+//! `COOLLED1248_FONT_CHARS` environment variable.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const FONT_DATA: &str = "assets/font_data.bin";
+const GLYPH_SIZE: usize = 32;
+const FONT_CHARS_ENV: &str = "COOLLED1248_FONT_CHARS";
+
+/// Inclusive codepoint ranges enabled by each feature
+const FEATURE_RANGES: &[(&str, &[(u16, u16)])] = &[
+    ("LATIN", &[(0x0020, 0x007e), (0x00a0, 0x00ff)]),
+    ("LATIN_EXT", &[(0x0100, 0x024f), (0x1e00, 0x1eff)]),
+    ("GREEK", &[(0x0370, 0x03ff)]),
+    ("CYRILLIC", &[(0x0400, 0x04ff)]),
+    (
+        "CJK",
+        &[(0x3000, 0x30ff), (0x4e00, 0x9fff), (0xff00, 0xffef)],
+    ),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={FONT_DATA}");
+    println!("cargo:rerun-if-env-changed={FONT_CHARS_ENV}");
+
+    if env::var_os("CARGO_FEATURE_FULL_FONT").is_some() {
+        return;
+    }
+
+    let mut codepoints = BTreeSet::new();
+
+    for (feature, ranges) in FEATURE_RANGES {
+        if env::var_os(format!("CARGO_FEATURE_{feature}")).is_some() {
+            for (start, end) in ranges.iter() {
+                codepoints.extend(*start..=*end);
+            }
+        }
+    }
+
+    if let Ok(chars) = env::var(FONT_CHARS_ENV) {
+        for c in chars.chars() {
+            match u16::try_from(c as u32) {
+                Ok(codepoint) => {
+                    codepoints.insert(codepoint);
+                }
+                Err(_) => println!("cargo:warning=no glyph for {c:?} in the bundled font"),
+            }
+        }
+    }
+
+    let font = fs::read(FONT_DATA).expect("unable to read the font data");
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+
+    // Same layout as `charset::Charset`
+    let mut charset = Vec::with_capacity(10 + codepoints.len() * (4 + GLYPH_SIZE));
+    charset.extend_from_slice(b"CLCS");
+    charset.push(1);
+    charset.push(GLYPH_SIZE as u8);
+    charset.extend_from_slice(&(codepoints.len() as u32).to_le_bytes());
+    for codepoint in &codepoints {
+        let addr = *codepoint as usize * GLYPH_SIZE;
+        charset.extend_from_slice(&(*codepoint as u32).to_le_bytes());
+        charset.extend_from_slice(&font[addr..addr + GLYPH_SIZE]);
+    }
+
+    fs::write(out_dir.join("font_subset.bin"), charset).unwrap();
+}
@@ -5,9 +5,10 @@ use clap::{command, Parser, ValueEnum};
 use coolled1248::colors::CoolLEDColors;
 use coolled1248::coolled::CoolLEDWriter;
 use coolled1248::coolled::PayloadType;
+use coolled1248::coolled::DEFAULT_ANIMATION_SPEED;
+use coolled1248::import::{gif_to_animation, image_to_payload, Dithering, ImportOptions};
 use coolled1248::packets::write_mode_led;
 use coolled1248::packets::EffectsMode;
-use image::{GenericImageView, Pixel};
 use log::info;
 use std::error::Error;
 use std::time::Duration;
@@ -63,198 +64,14 @@ struct Cli {
     adjust_bright: Option<u8>,
 }
 
-fn get_diff_color(cola: i32, colb: i32) -> i32 {
-    let (ar, ag, ab) = ((cola >> 16) & 0xff, (cola >> 8) & 0xff, cola & 0xff);
-    let (br, bg, bb) = ((colb >> 16) & 0xff, (colb >> 8) & 0xff, colb & 0xff);
-
-    let sum = (ar - br).pow(2) + (ag - bg).pow(2) + (ab - bb).pow(2);
-
-    (sum as f32).sqrt() as i32
-}
-
-const VALID_COLORS: [i32; 7] = [
-    0xffffff, //white
-    0xff0000, //red
-    0x00ff00, //green
-    0x0000ff, //blue
-    0xffff00, //yellow
-    0x00ffff, //cyan
-    0xff00ff, //pink
-];
-
-fn get_channel_from_gif(
-    filename: &str,
-    width: usize,
-    height: usize,
-) -> (Vec<u8>, Vec<u8>, Vec<u8>, usize) {
-    use image::buffer::ConvertBuffer;
-    use image::codecs::gif::GifDecoder;
-    use image::AnimationDecoder;
-    use std::fs::File;
-
-    let mut red = vec![];
-    let mut green = vec![];
-    let mut blue = vec![];
-
-    let file_in = File::open(filename).unwrap();
-    let decoder = GifDecoder::new(file_in).unwrap();
-    let frames = decoder.into_frames();
-    let frames = frames
-        .map(|f| f.unwrap().buffer().convert())
-        .collect::<Vec<image::RgbImage>>();
-
-    for frame in &frames {
-        let (mut frame_red, mut frame_green, mut frame_blue) = get_bytes(&frame, width, height);
-        red.append(&mut frame_red);
-        green.append(&mut frame_green);
-        blue.append(&mut frame_blue);
-    }
-
-    let frames_number = frames.len();
-    println!("frames qt {frames_number}");
-    (red, green, blue, frames_number)
-}
-
-fn get_bytes(img: &image::RgbImage, width: usize, height: usize) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-    let (image_witdh, image_height) = image::GenericImageView::dimensions(img);
-    let (image_witdh, image_height) = (image_witdh as usize, image_height as usize);
-
-    let default_color: image::Rgb<u8> = image::Rgb([0, 0, 0]);
-    let mut red = vec![];
-    let mut green = vec![];
-    let mut blue = vec![];
-
-    let mut tmp_red = 0;
-    let mut tmp_green = 0;
-    let mut tmp_blue = 0;
-    for x in 0..width {
-        for y in 0..height {
-            let mut is_default_color = false;
-            let px = if y < image_height && x < image_witdh {
-                img.get_pixel(x as u32, y as u32).to_rgb()
-            } else {
-                is_default_color = true;
-                default_color
-            };
-
-            let color_channels = px.channels();
-
-            let (tmp_red, tmp_green, tmp_blue) = if is_default_color {
-                tmp_red = (tmp_red << 1) + (color_channels[0] / 255);
-                tmp_green = (tmp_green << 1) + (color_channels[1] / 255);
-                tmp_blue = (tmp_blue << 1) + (color_channels[2] / 255);
-                (tmp_red, tmp_green, tmp_blue)
-            } else {
-                let mut diffs = vec![];
-                for vc in VALID_COLORS {
-                    let red = color_channels[0] as i32;
-                    let green = color_channels[1] as i32;
-                    let blue = color_channels[2] as i32;
-                    let cc = (red.wrapping_shl(16) + green.wrapping_shl(8) + blue).into();
-                    diffs.push(get_diff_color(vc, cc));
-                }
-
-                if let Some(min) = diffs.iter().min() {
-                    if let Some(pos_min) = diffs.iter().position(|x| x == min) {
-                        let closest_clor = VALID_COLORS[pos_min];
-                        let ccr = (closest_clor >> 16) & 0xff;
-                        let ccg = (closest_clor >> 8) & 0xff;
-                        let ccb = closest_clor & 0xff;
-                        tmp_red = (tmp_red << 1) + (ccr / 255) as u8;
-                        tmp_green = (tmp_green << 1) + (ccg / 255) as u8;
-                        tmp_blue = (tmp_blue << 1) + (ccb / 255) as u8;
-                    }
-                }
-
-                (tmp_red, tmp_green, tmp_blue)
-            };
-
-            if y % 8 == 7 {
-                red.push(tmp_red as u8);
-                green.push(tmp_green as u8);
-                blue.push(tmp_blue as u8);
-            }
-        }
-    }
-
-    (red, green, blue)
-}
-
-fn get_channels_from_image(
-    filename: &str,
-    width: usize,
-    height: usize,
-) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-    let img = image::open(&filename).unwrap();
+fn get_image_payload(filename: &str, width: usize, height: usize) -> Vec<u8> {
+    let img = image::open(filename).unwrap();
 
     let (image_witdh, image_height) = image::GenericImageView::dimensions(&img);
 
     println!("witdh : {} height : {}", &image_witdh, &image_height);
 
-    let (image_witdh, image_height) = (image_witdh as usize, image_height as usize);
-
-    let default_color: image::Rgb<u8> = image::Rgb([0, 0, 0]);
-    let mut red = vec![];
-    let mut green = vec![];
-    let mut blue = vec![];
-
-    let mut tmp_red = 0;
-    let mut tmp_green = 0;
-    let mut tmp_blue = 0;
-    for x in 0..width {
-        for y in 0..height {
-            let mut is_default_color = false;
-            let px = if y < image_height && x < image_witdh {
-                img.get_pixel(x as u32, y as u32).to_rgb()
-            } else {
-                is_default_color = true;
-                default_color
-            };
-
-            let color_channels = px.channels();
-
-            let (tmp_red, tmp_green, tmp_blue) = if is_default_color {
-                tmp_red = (tmp_red << 1) + (color_channels[0] / 255);
-                tmp_green = (tmp_green << 1) + (color_channels[1] / 255);
-                tmp_blue = (tmp_blue << 1) + (color_channels[2] / 255);
-                (tmp_red, tmp_green, tmp_blue)
-            } else {
-                let mut diffs = vec![];
-
-                for vc in VALID_COLORS {
-                    let red = color_channels[0] as i32;
-                    let green = color_channels[1] as i32;
-                    let blue = color_channels[2] as i32;
-                    let rgb_i32 = (red.wrapping_shl(16) + green.wrapping_shl(8) + blue).into();
-                    diffs.push(get_diff_color(vc, rgb_i32));
-                }
-
-                if let Some(min) = diffs.iter().min() {
-                    if let Some(pos_min) = diffs.iter().position(|x| x == min) {
-                        let closest_color = VALID_COLORS[pos_min];
-
-                        let closest_color_red = (closest_color >> 16) & 0xff;
-                        let closest_color_green = (closest_color >> 8) & 0xff;
-                        let closest_color_blue = closest_color & 0xff;
-
-                        tmp_red = (tmp_red << 1) + (closest_color_red / 255) as u8;
-                        tmp_green = (tmp_green << 1) + (closest_color_green / 255) as u8;
-                        tmp_blue = (tmp_blue << 1) + (closest_color_blue / 255) as u8;
-                    }
-                }
-
-                (tmp_red, tmp_green, tmp_blue)
-            };
-
-            if y % 8 == 7 {
-                red.push(tmp_red as u8);
-                green.push(tmp_green as u8);
-                blue.push(tmp_blue as u8);
-            }
-        }
-    }
-
-    (red, green, blue)
+    image_to_payload(&img, width, height, &ImportOptions::default())
 }
 
 #[tokio::main]
@@ -327,25 +144,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
             }
             Mode::Animation => {
                 let image = cli.image.unwrap();
-                let (red, green, blue, frames) =
-                    get_channel_from_gif(&image, cli.width, cli.height);
-                let animation_data: Vec<u8> = red
-                    .into_iter()
-                    .chain(green.into_iter())
-                    .chain(blue.into_iter())
-                    .collect();
-                let animation_data = Box::new(animation_data).leak();
-                CoolLEDWriter::new(PayloadType::Animation(animation_data, frames))
+                let file = std::io::BufReader::new(std::fs::File::open(image)?);
+                let options = ImportOptions {
+                    dithering: Dithering::Ordered,
+                    ..Default::default()
+                };
+                let animation = gif_to_animation(
+                    file,
+                    cli.width,
+                    cli.height,
+                    DEFAULT_ANIMATION_SPEED,
+                    &options,
+                )?;
+                println!("frames qt {}", animation.frames);
+                let animation = Box::leak(Box::new(animation));
+                animation.writer()
             }
             Mode::Image => {
                 let image = cli.image.unwrap();
-                let (red, green, blue) = get_channels_from_image(&image, cli.width, cli.height);
-
-                let color_data: Vec<u8> = red
-                    .into_iter()
-                    .chain(green.into_iter())
-                    .chain(blue.into_iter())
-                    .collect();
+                let color_data = get_image_payload(&image, cli.width, cli.height);
                 let color_data = Box::new(color_data).leak();
                 let led_writer = CoolLEDWriter::new(PayloadType::Image(color_data));
                 led_writer
@@ -1,5 +1,7 @@
-use std::io::Write;
+use coolled1248::charset::write_charset;
 use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::io::Write;
 use std::path::Path;
 
 #[derive(Debug,Deserialize)]
@@ -8,9 +10,9 @@ struct Config {
    outputfile: String,
 }
 
-fn read_original(c: char) -> &'static [u8] {
+fn read_original(c: char) -> &'static [u8; 32] {
     const FONT_DATA: &[u8; 2097152] = include_bytes!("../../assets/font_data.bin");
-    &FONT_DATA[(c as usize * ' ' as usize) as usize..(c as usize * ' ' as usize + 32) as usize]
+    FONT_DATA[c as usize * 32..c as usize * 32 + 32].try_into().unwrap()
 }
 
 fn main(){
@@ -19,12 +21,22 @@ fn main(){
 }
 
 fn extract_charset<P: AsRef<Path> + std::fmt::Debug>(charset: &str, output: P){
-    let mut out_file = std::fs::File::create(&output).unwrap();
-
-    let bytes_wrote : usize = charset
+    //The charset file needs the characters sorted and without duplicates
+    let (chars, missing): (BTreeSet<char>, BTreeSet<char>) = charset
         .chars()
-        .map(|char| out_file.write(read_original(char)).unwrap())
-        .sum();
+        .partition(|c| (*c as u32) <= 0xffff);
+    for c in &missing {
+        eprintln!("No glyph for {:?} (U+{:X}) in the bundled font, skipped", c, *c as u32);
+    }
 
-    println!("Wrote {} bytes at {}", bytes_wrote, output.as_ref().to_string_lossy());
-}
\ No newline at end of file
+    let mut data = vec![];
+    write_charset(
+        |byte| data.push(byte),
+        chars.iter().map(|c| (*c, read_original(*c))),
+    );
+
+    let mut out_file = std::fs::File::create(&output).unwrap();
+    out_file.write_all(&data).unwrap();
+
+    println!("Wrote {} characters ({} bytes) at {}", chars.len(), data.len(), output.as_ref().to_string_lossy());
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use anyhow::Result;
+use clap::Parser;
+use coolled1248::colors::CoolLEDColors;
+use coolled1248::coolled::{CoolLEDWriter, PayloadType};
+use coolled1248::markup;
+use coolled1248::preview::{Preview, PreviewStyle};
+
+#[derive(Parser)]
+#[command(author, version, about = "Render a text as shown on the panel", long_about = None)]
+struct Cli {
+    #[arg(help = "Text to render, colors are set with tags like {red}")]
+    text: String,
+
+    #[arg(short, long, default_value = "preview.png")]
+    output: String,
+
+    #[arg(long, default_value_t = 8, help = "Size in pixels of a LED")]
+    scale: u32,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let mut text = vec![0; cli.text.len()];
+    let mut colors = vec![CoolLEDColors::White; cli.text.len()];
+    let (text, colors) = markup::parse(&cli.text, &mut text, &mut colors)
+        .map_err(|error| anyhow::anyhow!("{}", error))?;
+
+    let writer = CoolLEDWriter::new(PayloadType::Text(text, colors));
+    let style = PreviewStyle {
+        scale: cli.scale,
+        ..Default::default()
+    };
+    Preview::new(&writer, 16).write_png(0, &style, BufWriter::new(File::create(&cli.output)?))?;
+
+    println!("wrote {}", cli.output);
+    Ok(())
+}
@@ -4,9 +4,11 @@ use std::time::Duration;
 use anyhow::Result;
 use clap::{command, Parser, ValueEnum};
 use coolled1248::colors::CoolLEDColors;
-use coolled1248::coolled::{CoolLEDWriter, PayloadType};
-use coolled1248::packets::get_init_packets;
-use image::Pixel;
+use coolled1248::coolled::{CoolLEDWriter, PayloadType, DEFAULT_ANIMATION_SPEED};
+use coolled1248::import::{gif_to_animation, image_to_payload, Dithering, ImportOptions};
+use coolled1248::orientation::Orientation;
+use coolled1248::packets::{get_init_packets, write_mode_led, EffectsMode};
+use coolled1248::preview::Preview;
 use log::*;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -18,6 +20,27 @@ enum Mode {
     ImageTest,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Effect {
+    Static,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl From<Effect> for EffectsMode {
+    fn from(effect: Effect) -> Self {
+        match effect {
+            Effect::Static => EffectsMode::Static,
+            Effect::Left => EffectsMode::Left,
+            Effect::Right => EffectsMode::Right,
+            Effect::Up => EffectsMode::Up,
+            Effect::Down => EffectsMode::Down,
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -38,99 +61,27 @@ struct Cli {
 
     #[arg(long)]
     image: Option<String>,
-}
-
-fn get_channel_from_gif(
-    filename: &str,
-    width: usize,
-    height: usize,
-) -> (Vec<u8>, Vec<u8>, Vec<u8>, usize) {
-    use image::buffer::ConvertBuffer;
-    use image::codecs::gif::GifDecoder;
-    use image::AnimationDecoder;
-    use std::fs::File;
-
-    let mut red = vec![];
-    let mut green = vec![];
-    let mut blue = vec![];
-
-    let file_in = File::open(filename).unwrap();
-    let decoder = GifDecoder::new(file_in).unwrap();
-    let frames = decoder.into_frames();
-    let frames = frames
-        .map(|f| f.unwrap().buffer().convert())
-        .collect::<Vec<image::RgbImage>>();
-
-    for frame in &frames {
-        let (mut frame_red, mut frame_green, mut frame_blue) = get_bytes(&frame, width, height);
-        red.append(&mut frame_red);
-        green.append(&mut frame_green);
-        blue.append(&mut frame_blue);
-    }
-
-    let frames_number = frames.len();
-    println!("frames qt {frames_number}");
-    (red, green, blue, frames_number)
-}
-
-fn get_bytes(img: &image::RgbImage, width: usize, height: usize) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-    let (image_witdh, image_height) = image::GenericImageView::dimensions(img);
-    let (image_witdh, image_height) = (image_witdh as usize, image_height as usize);
-
-    let default_color: image::Rgb<u8> = image::Rgb([0, 0, 0]);
-    let mut red = vec![];
-    let mut green = vec![];
-    let mut blue = vec![];
-
-    let mut tmp_red = 0;
-    let mut tmp_green = 0;
-    let mut tmp_blue = 0;
-    for x in 0..width {
-        for y in 0..height {
-            let px = if y < image_height && x < image_witdh {
-                img.get_pixel(x as u32, y as u32).to_rgb()
-            } else {
-                default_color
-            };
 
-            let color_channels = px.channels();
+    #[arg(long, help = "The panel is mounted upside down")]
+    upside_down: bool,
 
-            tmp_red = (tmp_red << 1) + (color_channels[0] / 255);
-            tmp_green = (tmp_green << 1) + (color_channels[1] / 255);
-            tmp_blue = (tmp_blue << 1) + (color_channels[2] / 255);
-
-            if y % 8 == 7 {
-                red.push(tmp_red);
-                green.push(tmp_green);
-                blue.push(tmp_blue);
-            }
-        }
-    }
+    #[arg(long, help = "Show the payload in the terminal instead of sending it")]
+    dry_run: bool,
 
-    (red, green, blue)
+    #[arg(long, value_enum, default_value_t = Effect::Left, help = "Effect of the text")]
+    effect: Effect,
 }
 
-fn get_channels_from_image(
-    filename: &str,
-    width: usize,
-    height: usize,
-) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-    let img = image::open(&filename).unwrap();
-    get_bytes(img.as_rgb8().unwrap(), width, height)
+fn get_image_payload(filename: &str, width: usize, height: usize) -> Vec<u8> {
+    let img = image::open(filename).unwrap();
+
+    image_to_payload(&img, width, height, &ImportOptions::default())
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     env_logger::init();
 
-    let mut serial_port = serialport::new("/dev/ttyUSB0", 38400).open().expect("err");
-
-    if cli.init {
-        get_init_packets(|data: u8| {
-            let _ = serial_port.write(&[data]);
-        });
-    }
-
     let pixels = cli.width * cli.height;
     let pixels_bytes = pixels / 8;
     const COLOR_CHANNELS: usize = 3;
@@ -166,29 +117,53 @@ fn main() -> Result<()> {
         }
         Mode::Animation => {
             let image = cli.image.unwrap();
-            let (red, green, blue, frames) = get_channel_from_gif(&image, cli.width, cli.height);
-            let animation_data: Vec<u8> = red
-                .into_iter()
-                .chain(green.into_iter())
-                .chain(blue.into_iter())
-                .collect();
-            let animation_data = Box::new(animation_data).leak();
-            CoolLEDWriter::new(PayloadType::Animation(animation_data, frames))
+            let file = std::io::BufReader::new(std::fs::File::open(image)?);
+            let options = ImportOptions {
+                dithering: Dithering::Ordered,
+                ..Default::default()
+            };
+            let animation = gif_to_animation(
+                file,
+                cli.width,
+                cli.height,
+                DEFAULT_ANIMATION_SPEED,
+                &options,
+            )?;
+            println!("frames qt {}", animation.frames);
+            let animation = Box::leak(Box::new(animation));
+            animation.writer()
         }
         Mode::Image => {
             let image = cli.image.unwrap();
-            let (red, green, blue) = get_channels_from_image(&image, cli.width, cli.height);
-
-            let color_data: Vec<u8> = red
-                .into_iter()
-                .chain(green.into_iter())
-                .chain(blue.into_iter())
-                .collect();
+            let color_data = get_image_payload(&image, cli.width, cli.height);
             let color_data = Box::new(color_data).leak();
             CoolLEDWriter::new(PayloadType::Image(color_data))
         }
     };
 
+    if cli.upside_down {
+        led_writer = led_writer.with_orientation(Orientation::ROTATE_180, cli.width, cli.height);
+    }
+    //The panel scrolls in its own directions
+    let effect = led_writer.orientation().effect(cli.effect.into());
+
+    if cli.dry_run {
+        let mut preview = Preview::new(&led_writer, cli.height);
+        if matches!(cli.mode, Mode::Text) {
+            preview = preview.with_effect(effect, cli.width, 50);
+        }
+        preview.play(std::io::stdout(), 1)?;
+        return Ok(());
+    }
+
+    let mut serial_port = serialport::new("/dev/ttyUSB0", 38400).open().expect("err");
+
+    if cli.init {
+        get_init_packets(|data: u8| {
+            let _ = serial_port.write(&[data]);
+        });
+    }
+
     let mut buffer: [u8; 255] = [0; 255];
     let quantity_packets = led_writer.get_packets_count();
     info!("packets = {}", quantity_packets);
@@ -219,5 +194,14 @@ fn main() -> Result<()> {
 
     info!("total : {}", total_written);
 
+    if matches!(cli.mode, Mode::Text) {
+        write_mode_led(
+            |data: u8| {
+                let _ = serial_port.write(&[data]);
+            },
+            effect,
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,265 @@
+//! Composition of animation payloads frame by frame.
+//!
+//! The data of an animation is the red plane of every frame, then the green
+//! planes and the blue ones. [`AnimationBuilder`] lays the frames out this way
+//! and checks the limits of the device, so the frame count always matches the
+//! data.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::canvas::{plane_size, Canvas};
+use crate::coolled::{
+    CoolLEDWriter, PayloadType, DEFAULT_ANIMATION_SPEED, MAX_ANIMATION_FRAMES, MAX_ANIMATION_SIZE,
+};
+
+/// Error returned when building an animation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationError {
+    /// The animation has no frame
+    Empty,
+    /// The delay between frames is 0
+    InvalidSpeed,
+    /// The animation has more frames than the device plays
+    TooManyFrames { frames: usize },
+    /// The animation data is larger than what a payload holds
+    TooLarge { size: usize },
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationError::Empty => f.write_str("the animation has no frame"),
+            AnimationError::InvalidSpeed => f.write_str("the delay between frames must not be 0"),
+            AnimationError::TooManyFrames { frames } => write!(
+                f,
+                "{} frames, the device plays at most {}",
+                frames, MAX_ANIMATION_FRAMES
+            ),
+            AnimationError::TooLarge { size } => write!(
+                f,
+                "{} bytes of animation data, a payload holds at most {}",
+                size, MAX_ANIMATION_SIZE
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AnimationError {}
+
+/// Animation ready to be sent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Animation {
+    /// Red planes of every frame, then the green ones and the blue ones
+    pub data: Vec<u8>,
+    pub frames: usize,
+    /// Delay between the frames in milliseconds
+    pub speed: u16,
+}
+
+impl Animation {
+    pub fn payload(&self) -> PayloadType<'_> {
+        PayloadType::Animation(&self.data, self.frames)
+    }
+
+    /// Writer sending the animation at its speed
+    pub fn writer(&self) -> CoolLEDWriter<'_> {
+        CoolLEDWriter::new(self.payload()).with_animation_speed(self.speed)
+    }
+}
+
+/// Builder of an animation of `W` by `H` pixels frames
+///
+/// ```
+/// use coolled1248::animation::AnimationBuilder;
+/// use coolled1248::canvas::Canvas;
+/// use coolled1248::colors::CoolLEDColors;
+///
+/// let mut frame = Canvas::<32, 16>::new();
+/// let mut builder = AnimationBuilder::new().with_speed(200);
+/// for x in 0..32 {
+///     frame.set_pixel(x, 8, CoolLEDColors::Green);
+///     builder.push_frame(&frame)?;
+/// }
+/// builder.push_frame_repeated(&frame, 5)?;
+///
+/// let animation = builder.build()?;
+/// assert_eq!(animation.frames, 37);
+/// # Ok::<(), coolled1248::animation::AnimationError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct AnimationBuilder<const W: usize, const H: usize> {
+    //Planes of the frames and how many times they are shown
+    frames: Vec<(Vec<u8>, usize)>,
+    count: usize,
+    speed: u16,
+}
+
+impl<const W: usize, const H: usize> Default for AnimationBuilder<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> AnimationBuilder<W, H> {
+    pub fn new() -> Self {
+        Self {
+            frames: Vec::new(),
+            count: 0,
+            speed: DEFAULT_ANIMATION_SPEED,
+        }
+    }
+
+    /// Set the delay between the frames in milliseconds, it must not be 0
+    pub fn with_speed(mut self, speed: u16) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Frames of the animation so far
+    pub fn frames(&self) -> usize {
+        self.count
+    }
+
+    /// Add a frame shown once
+    pub fn push_frame(&mut self, frame: &Canvas<W, H>) -> Result<&mut Self, AnimationError> {
+        self.push_frame_repeated(frame, 1)
+    }
+
+    /// Add a frame shown `times` times in a row. Fails without adding it when
+    /// the animation would exceed the limits of the device.
+    pub fn push_frame_repeated(
+        &mut self,
+        frame: &Canvas<W, H>,
+        times: usize,
+    ) -> Result<&mut Self, AnimationError> {
+        let count = self.count + times;
+        check_limits(count, Canvas::<W, H>::PLANE_SIZE)?;
+
+        if times > 0 {
+            let mut planes = alloc::vec![0; Canvas::<W, H>::SIZE];
+            frame.write_planes(&mut planes);
+            self.frames.push((planes, times));
+            self.count = count;
+        }
+        Ok(self)
+    }
+
+    pub fn build(&self) -> Result<Animation, AnimationError> {
+        assemble(
+            self.frames
+                .iter()
+                .map(|(planes, times)| (planes.as_slice(), *times)),
+            plane_size(W, H),
+            self.speed,
+        )
+    }
+}
+
+pub(crate) fn check_limits(frames: usize, plane_size: usize) -> Result<(), AnimationError> {
+    if frames > MAX_ANIMATION_FRAMES {
+        return Err(AnimationError::TooManyFrames { frames });
+    }
+    let size = frames * 3 * plane_size;
+    if size > MAX_ANIMATION_SIZE {
+        return Err(AnimationError::TooLarge { size });
+    }
+    Ok(())
+}
+
+/// Animation showing each frame (its three planes) the given number of times
+pub(crate) fn assemble<'f, I>(
+    frames: I,
+    plane_size: usize,
+    speed: u16,
+) -> Result<Animation, AnimationError>
+where
+    I: Iterator<Item = (&'f [u8], usize)> + Clone,
+{
+    if speed == 0 {
+        return Err(AnimationError::InvalidSpeed);
+    }
+    let count = frames.clone().map(|(_, times)| times).sum();
+    if count == 0 {
+        return Err(AnimationError::Empty);
+    }
+    check_limits(count, plane_size)?;
+
+    let mut data = Vec::with_capacity(count * 3 * plane_size);
+    for offset in (0..3).map(|plane| plane * plane_size) {
+        for (planes, times) in frames.clone() {
+            for _ in 0..times {
+                data.extend_from_slice(&planes[offset..offset + plane_size]);
+            }
+        }
+    }
+
+    Ok(Animation {
+        data,
+        frames: count,
+        speed,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colors::CoolLEDColors;
+
+    #[test]
+    fn frames_are_laid_out_by_plane() {
+        let mut red = Canvas::<2, 8>::new();
+        red.fill(CoolLEDColors::Red);
+        let mut blue = Canvas::<2, 8>::new();
+        blue.fill(CoolLEDColors::Blue);
+
+        let mut builder = AnimationBuilder::new().with_speed(100);
+        builder
+            .push_frame(&red)
+            .unwrap()
+            .push_frame_repeated(&blue, 2)
+            .unwrap()
+            .push_frame_repeated(&red, 0)
+            .unwrap();
+        assert_eq!(builder.frames(), 3);
+
+        let animation = builder.build().unwrap();
+        assert_eq!(animation.frames, 3);
+        assert_eq!(animation.speed, 100);
+        #[rustfmt::skip]
+        assert_eq!(
+            animation.data,
+            [
+                0xff, 0xff, 0, 0, 0, 0, //red
+                0, 0, 0, 0, 0, 0, //green
+                0, 0, 0xff, 0xff, 0xff, 0xff, //blue
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_are_checked() {
+        let frame = Canvas::<32, 16>::new();
+        let mut builder = AnimationBuilder::new();
+        assert_eq!(builder.build(), Err(AnimationError::Empty));
+
+        builder.push_frame_repeated(&frame, 255).unwrap();
+        assert_eq!(
+            builder.push_frame(&frame).err(),
+            Some(AnimationError::TooManyFrames { frames: 256 })
+        );
+        assert_eq!(builder.frames(), 255);
+        assert_eq!(builder.build().unwrap().data.len(), 255 * 192);
+
+        let mut wide = AnimationBuilder::<256, 16>::new();
+        assert_eq!(
+            wide.push_frame_repeated(&Canvas::new(), 50).err(),
+            Some(AnimationError::TooLarge { size: 50 * 1536 })
+        );
+
+        let mut still = AnimationBuilder::new().with_speed(0);
+        still.push_frame(&frame).unwrap();
+        assert_eq!(still.build(), Err(AnimationError::InvalidSpeed));
+    }
+}
@@ -0,0 +1,378 @@
+//! Framebuffer producing the bitplanes of image and animation payloads.
+//!
+//! An image is sent as three planes, red then green then blue. Each plane is
+//! column major, every column being `ceil(height / 8)` bytes with the top pixel
+//! in the most significant bit of its first byte. When the height is not a
+//! multiple of 8 the last byte of each column is padded with zeros.
+
+use crate::colors::CoolLEDColors;
+use crate::font::{Font, GlyphProvider};
+use crate::layout::Align;
+
+/// Planes of an image, in the order they are sent
+pub const PLANES: [CoolLEDColors; 3] = [
+    CoolLEDColors::Red,
+    CoolLEDColors::Green,
+    CoolLEDColors::Blue,
+];
+
+/// Bytes of one plane of an image of `width` by `height` pixels
+pub const fn plane_size(width: usize, height: usize) -> usize {
+    width * height.div_ceil(8)
+}
+
+/// Write in `out` the bits of `plane` (red, green or blue) for the image of
+/// `width` by `height` pixels whose colors are returned by `pixel(x, y)`.
+/// Returns the number of bytes written.
+pub fn pack_plane<F: Fn(usize, usize) -> CoolLEDColors>(
+    width: usize,
+    height: usize,
+    plane: CoolLEDColors,
+    pixel: F,
+    out: &mut [u8],
+) -> usize {
+    let mut wrote = 0;
+    for x in 0..width {
+        for group in (0..height).step_by(8) {
+            let mut byte = 0u8;
+            for bit in 0..8 {
+                let y = group + bit;
+                if y < height && pixel(x, y).has(plane) {
+                    byte |= 0x80 >> bit;
+                }
+            }
+            out[wrote] = byte;
+            wrote += 1;
+        }
+    }
+    wrote
+}
+
+/// Write in `out` the three planes of the image of `width` by `height` pixels
+/// whose colors are returned by `pixel(x, y)`. Returns the number of bytes
+/// written.
+pub fn pack_planes<F: Fn(usize, usize) -> CoolLEDColors>(
+    width: usize,
+    height: usize,
+    pixel: F,
+    out: &mut [u8],
+) -> usize {
+    PLANES.iter().fold(0, |wrote, plane| {
+        wrote + pack_plane(width, height, *plane, &pixel, &mut out[wrote..])
+    })
+}
+
+/// A line of text drawn by [`Canvas::draw_lines`]
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'t> {
+    pub text: &'t str,
+    pub color: CoolLEDColors,
+    pub align: Align,
+}
+
+impl<'t> Line<'t> {
+    /// A left aligned line
+    pub fn new(text: &'t str, color: CoolLEDColors) -> Self {
+        Self {
+            text,
+            color,
+            align: Align::Left,
+        }
+    }
+
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+}
+
+/// Framebuffer of `W` columns by `H` rows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Canvas<const W: usize, const H: usize> {
+    pixels: [[CoolLEDColors; W]; H],
+}
+
+impl<const W: usize, const H: usize> Default for Canvas<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> Canvas<W, H> {
+    /// Bytes of one plane
+    pub const PLANE_SIZE: usize = plane_size(W, H);
+    /// Bytes of the three planes, the size of an image payload
+    pub const SIZE: usize = 3 * Self::PLANE_SIZE;
+
+    /// An all black canvas
+    pub const fn new() -> Self {
+        Self {
+            pixels: [[CoolLEDColors::Black; W]; H],
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        W
+    }
+
+    pub const fn height(&self) -> usize {
+        H
+    }
+
+    /// Color of a pixel, `None` outside of the canvas
+    pub fn pixel(&self, x: i32, y: i32) -> Option<CoolLEDColors> {
+        let (x, y) = Self::index(x, y)?;
+        Some(self.pixels[y][x])
+    }
+
+    /// Set the color of a pixel, pixels outside of the canvas are ignored
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: CoolLEDColors) {
+        if let Some((x, y)) = Self::index(x, y) {
+            self.pixels[y][x] = color;
+        }
+    }
+
+    pub fn fill(&mut self, color: CoolLEDColors) {
+        self.pixels.iter_mut().for_each(|row| row.fill(color));
+    }
+
+    /// Fill a rectangle, clipped to the canvas
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: usize, height: usize, color: CoolLEDColors) {
+        for dy in 0..height as i32 {
+            for dx in 0..width as i32 {
+                self.set_pixel(x + dx, y + dy, color);
+            }
+        }
+    }
+
+    /// Turn every pixel off
+    pub fn clear(&mut self) {
+        self.fill(CoolLEDColors::Black);
+    }
+
+    /// Copy `src` with its top left corner at `(x, y)`, clipped to the canvas
+    pub fn blit<const SW: usize, const SH: usize>(&mut self, src: &Canvas<SW, SH>, x: i32, y: i32) {
+        for (sy, row) in src.pixels.iter().enumerate() {
+            for (sx, color) in row.iter().enumerate() {
+                self.set_pixel(x + sx as i32, y + sy as i32, *color);
+            }
+        }
+    }
+
+    /// Draw `text` with its top left corner at `(x, y)`, character `i` in
+    /// `colors[i % colors.len()]` like text payloads. Only the lit pixels are
+    /// drawn. Returns the column following the text.
+    ///
+    /// Panics if `colors` is empty.
+    pub fn draw_text<G: GlyphProvider>(
+        &mut self,
+        font: &Font<'_, G>,
+        text: &str,
+        x: i32,
+        y: i32,
+        colors: &[CoolLEDColors],
+    ) -> i32 {
+        self.draw_text_with(font, text, x, y, |idx, _, _| colors[idx % colors.len()])
+    }
+
+    /// Draw `text` with its top left corner at `(x, y)`, the color of each lit
+    /// pixel being `paint(char index, x, y)`. Returns the column following the
+    /// text.
+    pub fn draw_text_with<G: GlyphProvider, F: FnMut(usize, i32, i32) -> CoolLEDColors>(
+        &mut self,
+        font: &Font<'_, G>,
+        text: &str,
+        x: i32,
+        y: i32,
+        mut paint: F,
+    ) -> i32 {
+        let mut cursor = x;
+        for (idx, c) in text.chars().enumerate() {
+            if idx > 0 {
+                cursor += font.style().gap as i32;
+            }
+            for column in font.char_columns(c) {
+                let bits = u16::from_be_bytes(column);
+                for row in (0..16).filter(|row| bits & (0x8000 >> row) != 0) {
+                    let color = paint(idx, cursor, y + row);
+                    self.set_pixel(cursor, y + row, color);
+                }
+                cursor += 1;
+            }
+        }
+        cursor
+    }
+
+    /// Draw `lines` one below the other, each `line_height` rows tall and
+    /// aligned in the width of the canvas. With the small font two lines fit
+    /// on the panel:
+    ///
+    /// ```
+    /// use coolled1248::canvas::{Canvas, Line};
+    /// use coolled1248::colors::CoolLEDColors;
+    /// use coolled1248::font::Font;
+    /// use coolled1248::layout::Align;
+    /// use coolled1248::small_font::{SmallFont, LINE_HEIGHT, STYLE};
+    ///
+    /// let font = Font::new(SmallFont).with_style(STYLE);
+    /// let mut canvas = Canvas::<32, 16>::new();
+    /// canvas.draw_lines(
+    ///     &font,
+    ///     &[
+    ///         Line::new("CPU", CoolLEDColors::Cyan),
+    ///         Line::new("42%", CoolLEDColors::Yellow).with_align(Align::Right),
+    ///     ],
+    ///     LINE_HEIGHT,
+    /// );
+    ///
+    /// let mut payload = [0; Canvas::<32, 16>::SIZE];
+    /// canvas.write_planes(&mut payload);
+    /// ```
+    pub fn draw_lines<G: GlyphProvider>(
+        &mut self,
+        font: &Font<'_, G>,
+        lines: &[Line<'_>],
+        line_height: usize,
+    ) {
+        for (idx, line) in lines.iter().enumerate() {
+            let x = line.align.offset(font.measure(line.text), W);
+            let y = idx * line_height;
+            self.draw_text(font, line.text, x as i32, y as i32, &[line.color]);
+        }
+    }
+
+    /// Write the bits of `plane` (red, green or blue) in `out`, returns
+    /// [`Self::PLANE_SIZE`]
+    pub fn write_plane(&self, plane: CoolLEDColors, out: &mut [u8]) -> usize {
+        pack_plane(W, H, plane, |x, y| self.pixels[y][x], out)
+    }
+
+    /// Write the three planes in `out`, ready for `PayloadType::Image`.
+    /// Returns [`Self::SIZE`]
+    pub fn write_planes(&self, out: &mut [u8]) -> usize {
+        pack_planes(W, H, |x, y| self.pixels[y][x], out)
+    }
+
+    fn index(x: i32, y: i32) -> Option<(usize, usize)> {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        if x < W && y < H {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::small_font::{self, SmallFont};
+
+    #[test]
+    fn planes_layout() {
+        let mut canvas = Canvas::<2, 16>::new();
+        canvas.set_pixel(0, 0, CoolLEDColors::Red);
+        canvas.set_pixel(0, 15, CoolLEDColors::Yellow);
+        canvas.set_pixel(1, 9, CoolLEDColors::Cyan);
+
+        let mut out = [0xaa; Canvas::<2, 16>::SIZE];
+        assert_eq!(canvas.write_planes(&mut out), 12);
+        assert_eq!(
+            out,
+            [
+                0x80, 0x01, 0x00, 0x00, //red
+                0x00, 0x01, 0x00, 0x40, //green
+                0x00, 0x00, 0x00, 0x40, //blue
+            ]
+        );
+    }
+
+    #[test]
+    fn height_not_multiple_of_eight() {
+        let mut canvas = Canvas::<2, 12>::new();
+        canvas.fill(CoolLEDColors::Blue);
+
+        let mut out = [0xaa; 12];
+        assert_eq!(canvas.write_plane(CoolLEDColors::Blue, &mut out), 4);
+        assert_eq!(&out[..4], &[0xff, 0xf0, 0xff, 0xf0]);
+        assert_eq!(canvas.write_plane(CoolLEDColors::Red, &mut out), 4);
+        assert_eq!(&out[..4], &[0; 4]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn draw_text_at_offset() {
+        let font = Font::new(crate::font::UnicodeFont);
+        let mut canvas = Canvas::<32, 16>::new();
+
+        let end = canvas.draw_text(
+            &font,
+            "Ti",
+            2,
+            0,
+            &[CoolLEDColors::Red, CoolLEDColors::Blue],
+        );
+        assert_eq!(end, 2 + font.measure("Ti") as i32);
+
+        //The bar of the 'T' on the third row, over its 7 columns
+        assert_eq!(canvas.pixel(1, 2), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(2, 2), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(8, 2), Some(CoolLEDColors::Red));
+        assert!((10..end).any(|x| (0..16).any(|y| canvas.pixel(x, y) == Some(CoolLEDColors::Blue))));
+
+        //Shifted down, the bottom rows are clipped
+        let mut shifted = Canvas::<32, 16>::new();
+        shifted.draw_text_with(&font, "Ti", 2, 3, |_, x, _| {
+            if x < 9 {
+                CoolLEDColors::Red
+            } else {
+                CoolLEDColors::Blue
+            }
+        });
+        for y in 0..13 {
+            for x in 0..32 {
+                assert_eq!(shifted.pixel(x, y + 3), canvas.pixel(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn two_small_lines() {
+        let font = Font::new(SmallFont).with_style(small_font::STYLE);
+        let mut canvas = Canvas::<32, 16>::new();
+        canvas.draw_lines(
+            &font,
+            &[
+                Line::new("T", CoolLEDColors::Red),
+                Line::new("T", CoolLEDColors::Green).with_align(Align::Right),
+            ],
+            small_font::LINE_HEIGHT,
+        );
+
+        assert_eq!(canvas.pixel(0, 0), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(2, 6), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(2, 7), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(27, 8), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(31, 8), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(29, 14), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(26, 8), Some(CoolLEDColors::Black));
+    }
+
+    #[test]
+    fn blit_is_clipped() {
+        let mut src = Canvas::<3, 3>::new();
+        src.fill(CoolLEDColors::Green);
+
+        let mut canvas = Canvas::<4, 4>::new();
+        canvas.blit(&src, -1, 2);
+
+        assert_eq!(canvas.pixel(0, 1), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(0, 2), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(1, 3), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(2, 3), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(4, 0), None);
+
+        canvas.clear();
+        assert_eq!(canvas, Canvas::new());
+    }
+}
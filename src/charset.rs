@@ -0,0 +1,251 @@
+//! Self describing charset files, a compact alternative to the bundled font.
+//!
+//! A charset file is a 10 bytes header followed by one record per character.
+//!
+//! | offset | size | content                                  |
+//! |--------|------|------------------------------------------|
+//! | 0      | 4    | magic `CLCS`                             |
+//! | 4      | 1    | format version, currently `1`            |
+//! | 5      | 1    | glyph size in bytes, always `32`         |
+//! | 6      | 4    | number of records, little endian         |
+//!
+//! Each record is the codepoint of the character (4 bytes, little endian)
+//! followed by its glyph. Records are sorted by codepoint without duplicates,
+//! so a character is found with a binary search.
+
+use core::fmt;
+
+use crate::font::GlyphProvider;
+
+pub const MAGIC: [u8; 4] = *b"CLCS";
+pub const VERSION: u8 = 1;
+pub const GLYPH_SIZE: usize = 32;
+pub const HEADER_SIZE: usize = 10;
+pub const RECORD_SIZE: usize = 4 + GLYPH_SIZE;
+
+/// Errors found while parsing a charset file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharsetError {
+    /// The data does not start with `CLCS`
+    BadMagic,
+    /// The format version is not supported by this crate
+    UnsupportedVersion(u8),
+    /// Only 32 bytes (16x16) glyphs are supported
+    UnsupportedGlyphSize(u8),
+    /// The data length does not match the number of records of the header
+    InvalidLength { expected: usize, found: usize },
+    /// The record at `index` does not hold a valid unicode scalar value
+    InvalidCodepoint { index: usize, codepoint: u32 },
+    /// The record at `index` is not greater than the previous one
+    Unsorted { index: usize },
+}
+
+impl fmt::Display for CharsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharsetError::BadMagic => write!(f, "not a charset file"),
+            CharsetError::UnsupportedVersion(version) => {
+                write!(f, "unsupported charset version {}", version)
+            }
+            CharsetError::UnsupportedGlyphSize(size) => {
+                write!(f, "unsupported glyph size of {} bytes", size)
+            }
+            CharsetError::InvalidLength { expected, found } => {
+                write!(f, "expected {} bytes of charset, found {}", expected, found)
+            }
+            CharsetError::InvalidCodepoint { index, codepoint } => {
+                write!(f, "invalid codepoint {:#x} at record {}", codepoint, index)
+            }
+            CharsetError::Unsorted { index } => {
+                write!(f, "record {} is duplicated or out of order", index)
+            }
+        }
+    }
+}
+
+/// A validated charset file
+#[derive(Clone, Copy, Debug)]
+pub struct Charset<'a> {
+    records: &'a [u8],
+}
+
+impl<'a> Charset<'a> {
+    /// Parse and validate a charset file
+    pub fn parse(data: &'a [u8]) -> Result<Self, CharsetError> {
+        if data.len() < HEADER_SIZE || data[0..4] != MAGIC {
+            return Err(CharsetError::BadMagic);
+        }
+        if data[4] != VERSION {
+            return Err(CharsetError::UnsupportedVersion(data[4]));
+        }
+        if data[5] as usize != GLYPH_SIZE {
+            return Err(CharsetError::UnsupportedGlyphSize(data[5]));
+        }
+
+        let count = u32::from_le_bytes([data[6], data[7], data[8], data[9]]) as usize;
+        let expected = count
+            .checked_mul(RECORD_SIZE)
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .unwrap_or(usize::MAX);
+        if data.len() != expected {
+            return Err(CharsetError::InvalidLength {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        let charset = Self {
+            records: &data[HEADER_SIZE..],
+        };
+
+        let mut previous = None;
+        for index in 0..count {
+            let codepoint = charset.codepoint(index);
+            if char::from_u32(codepoint).is_none() {
+                return Err(CharsetError::InvalidCodepoint { index, codepoint });
+            }
+            if previous.is_some_and(|previous| previous >= codepoint) {
+                return Err(CharsetError::Unsorted { index });
+            }
+            previous = Some(codepoint);
+        }
+
+        Ok(charset)
+    }
+
+    /// Use a charset file without validating it.
+    ///
+    /// Lookups on invalid records return wrong glyphs but never panic. Panics
+    /// if `data` is shorter than the header.
+    pub const fn from_bytes_unchecked(data: &'a [u8]) -> Self {
+        let (_, records) = data.split_at(HEADER_SIZE);
+        Self { records }
+    }
+
+    /// Number of characters in the charset
+    pub fn len(&self) -> usize {
+        self.records.len() / RECORD_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Characters of the charset, in codepoint order
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        (0..self.len()).filter_map(|index| char::from_u32(self.codepoint(index)))
+    }
+
+    /// Return the glyph of `c` if it is in the charset
+    pub fn get(&self, c: char) -> Option<&'a [u8]> {
+        let needle = c as u32;
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.codepoint(mid).cmp(&needle) {
+                core::cmp::Ordering::Less => low = mid + 1,
+                core::cmp::Ordering::Greater => high = mid,
+                core::cmp::Ordering::Equal => {
+                    let addr = mid * RECORD_SIZE + 4;
+                    return Some(&self.records[addr..addr + GLYPH_SIZE]);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn codepoint(&self, index: usize) -> u32 {
+        let addr = index * RECORD_SIZE;
+        let bytes = &self.records[addr..addr + 4];
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+impl<'a> GlyphProvider for Charset<'a> {
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        self.get(c)
+    }
+}
+
+/// Write a charset file.
+///
+/// The glyphs must be sorted by character and without duplicates, otherwise
+/// [`Charset::parse`] rejects the result.
+pub fn write_charset<'g, F, I>(mut func: F, glyphs: I)
+where
+    F: FnMut(u8),
+    I: IntoIterator<Item = (char, &'g [u8; GLYPH_SIZE])>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let glyphs = glyphs.into_iter();
+
+    MAGIC.iter().for_each(|byte| func(*byte));
+    func(VERSION);
+    func(GLYPH_SIZE as u8);
+    (glyphs.len() as u32)
+        .to_le_bytes()
+        .iter()
+        .for_each(|byte| func(*byte));
+
+    for (c, glyph) in glyphs {
+        (c as u32).to_le_bytes().iter().for_each(|byte| func(*byte));
+        glyph.iter().for_each(|byte| func(*byte));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate alloc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn build(chars: &[char]) -> Vec<u8> {
+        let glyphs: Vec<[u8; GLYPH_SIZE]> = chars.iter().map(|c| [*c as u8; GLYPH_SIZE]).collect();
+        let mut out = vec![];
+        write_charset(
+            |byte| out.push(byte),
+            chars.iter().zip(glyphs.iter()).map(|(c, g)| (*c, g)),
+        );
+        out
+    }
+
+    #[test]
+    fn roundtrip() {
+        let data = build(&['0', 'A', 'a', 'é']);
+        let charset = Charset::parse(&data).unwrap();
+
+        assert_eq!(charset.len(), 4);
+        assert_eq!(charset.get('A'), Some(&[b'A'; GLYPH_SIZE][..]));
+        assert_eq!(charset.get('é'), Some(&[0xe9; GLYPH_SIZE][..]));
+        assert_eq!(charset.get('b'), None);
+        assert_eq!(charset.glyph('b'), None);
+        assert!(charset.chars().eq(['0', 'A', 'a', 'é']));
+    }
+
+    #[test]
+    fn rejects_duplicates() {
+        let data = build(&['\'', '\'', 'A']);
+        assert_eq!(Charset::parse(&data).unwrap_err(), CharsetError::Unsorted { index: 1 });
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let mut data = build(&['A']);
+        assert_eq!(
+            Charset::parse(&data[..data.len() - 1]).unwrap_err(),
+            CharsetError::InvalidLength {
+                expected: HEADER_SIZE + RECORD_SIZE,
+                found: HEADER_SIZE + RECORD_SIZE - 1
+            }
+        );
+
+        data[4] = 2;
+        assert_eq!(Charset::parse(&data).unwrap_err(), CharsetError::UnsupportedVersion(2));
+
+        data[0] = b'X';
+        assert_eq!(Charset::parse(&data).unwrap_err(), CharsetError::BadMagic);
+    }
+}
@@ -1,4 +1,7 @@
-#[derive(PartialEq, Clone, Copy, Debug)]
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum CoolLEDColors {
     White,
     Red,
@@ -7,8 +10,21 @@ pub enum CoolLEDColors {
     Yellow,
     Pink,
     Cyan,
+    Black,
 }
 
+/// Every color the device can display
+pub const PALETTE: [CoolLEDColors; 8] = [
+    CoolLEDColors::White,
+    CoolLEDColors::Red,
+    CoolLEDColors::Green,
+    CoolLEDColors::Blue,
+    CoolLEDColors::Yellow,
+    CoolLEDColors::Pink,
+    CoolLEDColors::Cyan,
+    CoolLEDColors::Black,
+];
+
 impl CoolLEDColors {
     pub fn has(&self, color: CoolLEDColors) -> bool {
         match &self {
@@ -19,6 +35,169 @@ impl CoolLEDColors {
             CoolLEDColors::Yellow => color == CoolLEDColors::Red || color == CoolLEDColors::Green,
             CoolLEDColors::Pink => color == CoolLEDColors::Red || color == CoolLEDColors::Blue,
             CoolLEDColors::Cyan => color == CoolLEDColors::Blue || color == CoolLEDColors::Green,
+            CoolLEDColors::Black => false,
+        }
+    }
+
+    /// Bits of the red, green and blue planes, in this order
+    pub fn planes(&self) -> impl Iterator<Item = bool> {
+        let color = *self;
+        [
+            CoolLEDColors::Red,
+            CoolLEDColors::Green,
+            CoolLEDColors::Blue,
+        ]
+        .into_iter()
+        .map(move |plane| color.has(plane))
+    }
+
+    /// Color lit by the given red, green and blue plane bits
+    pub fn from_planes(red: bool, green: bool, blue: bool) -> Self {
+        match (red, green, blue) {
+            (true, true, true) => CoolLEDColors::White,
+            (true, false, false) => CoolLEDColors::Red,
+            (false, true, false) => CoolLEDColors::Green,
+            (false, false, true) => CoolLEDColors::Blue,
+            (true, true, false) => CoolLEDColors::Yellow,
+            (true, false, true) => CoolLEDColors::Pink,
+            (false, true, true) => CoolLEDColors::Cyan,
+            (false, false, false) => CoolLEDColors::Black,
+        }
+    }
+
+    /// Red, green and blue components of the color
+    pub fn to_rgb(&self) -> [u8; 3] {
+        let mut rgb = [0; 3];
+        for (component, lit) in rgb.iter_mut().zip(self.planes()) {
+            *component = if lit { 0xff } else { 0 };
+        }
+        rgb
+    }
+
+    /// Closest color of the palette to the given red, green and blue components
+    pub fn from_rgb(rgb: [u8; 3]) -> Self {
+        let distance = |color: &CoolLEDColors| {
+            color
+                .to_rgb()
+                .iter()
+                .zip(rgb.iter())
+                .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+                .sum::<i32>()
+        };
+
+        PALETTE
+            .iter()
+            .copied()
+            .min_by_key(distance)
+            .unwrap_or(CoolLEDColors::Black)
+    }
+
+    /// Color as a `0xRRGGBB` value
+    pub fn to_rgb888(&self) -> u32 {
+        let [red, green, blue] = self.to_rgb();
+        u32::from_be_bytes([0, red, green, blue])
+    }
+
+    /// Closest color of the palette to a `0xRRGGBB` value
+    pub fn from_rgb888(rgb: u32) -> Self {
+        let [_, red, green, blue] = rgb.to_be_bytes();
+        Self::from_rgb([red, green, blue])
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoolLEDColors::White => "white",
+            CoolLEDColors::Red => "red",
+            CoolLEDColors::Green => "green",
+            CoolLEDColors::Blue => "blue",
+            CoolLEDColors::Yellow => "yellow",
+            CoolLEDColors::Pink => "pink",
+            CoolLEDColors::Cyan => "cyan",
+            CoolLEDColors::Black => "black",
         }
     }
 }
+
+impl fmt::Display for CoolLEDColors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error returned when parsing an unknown color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseColorError;
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unknown color, expected a color name or #rrggbb")
+    }
+}
+
+impl FromStr for CoolLEDColors {
+    type Err = ParseColorError;
+
+    /// Parse a color name, in any case, or the closest color to a `#rrggbb`
+    /// value. `magenta` and `off` are aliases of pink and black.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ParseColorError);
+            }
+            return u32::from_str_radix(hex, 16)
+                .map(Self::from_rgb888)
+                .map_err(|_| ParseColorError);
+        }
+
+        if s.eq_ignore_ascii_case("magenta") {
+            return Ok(CoolLEDColors::Pink);
+        }
+        if s.eq_ignore_ascii_case("off") {
+            return Ok(CoolLEDColors::Black);
+        }
+
+        PALETTE
+            .iter()
+            .find(|color| color.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or(ParseColorError)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rgb_roundtrip() {
+        for color in PALETTE {
+            assert_eq!(CoolLEDColors::from_rgb(color.to_rgb()), color);
+            assert_eq!(CoolLEDColors::from_rgb888(color.to_rgb888()), color);
+
+            let mut planes = color.planes();
+            let (red, green, blue) = (planes.next(), planes.next(), planes.next());
+            assert_eq!(
+                CoolLEDColors::from_planes(red.unwrap(), green.unwrap(), blue.unwrap()),
+                color
+            );
+        }
+
+        assert_eq!(CoolLEDColors::Yellow.to_rgb888(), 0xffff00);
+        assert_eq!(CoolLEDColors::from_rgb([200, 30, 40]), CoolLEDColors::Red);
+        assert_eq!(CoolLEDColors::from_rgb([40, 40, 60]), CoolLEDColors::Black);
+    }
+
+    #[test]
+    fn parse_names() {
+        for color in PALETTE {
+            let name = color.name();
+            assert_eq!(name.parse::<CoolLEDColors>(), Ok(color));
+        }
+
+        assert_eq!("Cyan".parse(), Ok(CoolLEDColors::Cyan));
+        assert_eq!("magenta".parse(), Ok(CoolLEDColors::Pink));
+        assert_eq!("#f0e010".parse(), Ok(CoolLEDColors::Yellow));
+        assert_eq!("#f0e0".parse::<CoolLEDColors>(), Err(ParseColorError));
+        assert_eq!("purple".parse::<CoolLEDColors>(), Err(ParseColorError));
+    }
+}
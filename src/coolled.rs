@@ -2,7 +2,10 @@ use core::ops::Shr;
 
 use crate::{
     colors::CoolLEDColors,
+    font::{cycle_color, Fallback, Font, GlyphProvider, TextStyle, UnicodeFont},
+    layout::Align,
     ncycle::Ncycles,
+    orientation::{Orientation, MAX_COLUMN_SIZE},
     packets::PacketType,
     util::{calculate_checksum, escape_byets_in_place},
 };
@@ -10,6 +13,16 @@ use crate::{
 const TEXT_PREFIX_FIRST_PACKET_HEADER_SIZE: usize = 107;
 const IMAGE_PREFIX_FIRST_PACKET_HEADER_SIZE: usize = 26;
 const ANIMATION_PREFIX_FIRST_PACKET_HEADER_SIZE: usize = 27;
+//Rows of the text payload columns
+const TEXT_HEIGHT: usize = 16;
+
+/// Most frames of an animation, their count is sent in a single byte
+pub const MAX_ANIMATION_FRAMES: usize = 255;
+/// Most bytes of animation data, the size of the whole payload is sent in two
+/// bytes
+pub const MAX_ANIMATION_SIZE: usize = 0xffff - ANIMATION_PREFIX_FIRST_PACKET_HEADER_SIZE;
+/// Delay between the frames of an animation when none is set, in milliseconds
+pub const DEFAULT_ANIMATION_SPEED: u16 = 500;
 
 pub enum PayloadType<'b> {
     //Text data, slice of colors
@@ -30,47 +43,168 @@ impl<'b> PayloadType<'b> {
     }
 }
 
-pub struct CoolLEDWriter<'a> {
+pub struct CoolLEDWriter<'a, G = UnicodeFont> {
     payload: PayloadType<'a>,
-    #[cfg(feature = "custom_charset")]
-    pub(crate) custom_charset: &'a [u8],
-    #[cfg(feature = "custom_charset")]
-    pub(crate) custom_charset_list: &'a str,
+    pub(crate) font: Font<'a, G>,
+    //Alignment of the text payload and width of the panel it is aligned in
+    alignment: (Align, usize),
+    animation_speed: u16,
+    orientation: Orientation,
+    panel_width: usize,
+    panel_height: usize,
 }
 
-
 impl<'a> CoolLEDWriter<'a> {
-    pub fn get_packets_count(&self) -> usize {
-        (self.get_total_bytes_from_phrase_data() + self.get_padding()).div_ceil(128)
+    pub fn new(payload: PayloadType<'a>) -> Self {
+        Self::with_glyphs(payload, UnicodeFont)
     }
+}
 
-    fn get_total_bytes_from_phrase_data(&self) -> usize {
-        match self.payload {
-            PayloadType::Text(phrase,_) => phrase
-                .chars()
-                .map(|c| {
-                    let mut buf: [u8; 16] = [0; 16];
-                    self.get_font_byte_trimmed(c, 2, &mut buf) * 3
-                })
-                .sum(),
-            PayloadType::Image(data) => data.len(),
-            PayloadType::Animation(data,_) => data.len(),
-        }
+impl<'a, G: GlyphProvider> CoolLEDWriter<'a, G> {
+    /// Create a writer that draws text with the given glyphs
+    pub fn with_glyphs(payload: PayloadType<'a>, glyphs: G) -> Self {
+        Self::with_font(payload, Font::new(glyphs))
     }
 
-    #[cfg(not(feature = "custom_charset"))]
-    pub fn new(payload: PayloadType<'a>) -> Self {
+    /// Create a writer that draws text with the given font
+    pub fn with_font(payload: PayloadType<'a>, font: Font<'a, G>) -> Self {
         Self {
             payload,
+            font,
+            alignment: (Align::Left, 0),
+            animation_speed: DEFAULT_ANIMATION_SPEED,
+            orientation: Orientation::NORMAL,
+            panel_width: 0,
+            panel_height: TEXT_HEIGHT,
         }
     }
 
-    #[cfg(feature = "custom_charset")]
-    pub fn new(payload: PayloadType<'a>, custom_charset: &'a [u8], custom_charset_list: &'a str) -> Self {
-        Self {
-            payload,
-            custom_charset,
-            custom_charset_list
+    /// Set what is drawn for the characters missing in the font
+    pub fn with_fallback(mut self, fallback: Fallback<'a>) -> Self {
+        self.font.set_fallback(fallback);
+        self
+    }
+
+    /// Set how the characters of the text payload are laid out
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.font.set_style(style);
+        self
+    }
+
+    /// Align the text payload inside a panel of `panel_width` columns, by
+    /// adding blank columns before it. Texts wider than the panel are left
+    /// aligned.
+    pub fn with_alignment(mut self, align: Align, panel_width: usize) -> Self {
+        self.alignment = (align, panel_width);
+        self
+    }
+
+    /// Set the delay between the frames of the animation payload, in
+    /// milliseconds
+    pub fn with_animation_speed(mut self, speed: u16) -> Self {
+        self.animation_speed = speed;
+        self
+    }
+
+    /// Turn the payload for a panel of `panel_width` by `panel_height`
+    /// pixels. Text payloads are then sent as an image of the text, at least
+    /// as wide as the panel.
+    ///
+    /// Image and animation payloads must be `panel_height` rows tall, their
+    /// planes are turned as such and come out scrambled otherwise.
+    pub fn with_orientation(
+        mut self,
+        orientation: Orientation,
+        panel_width: usize,
+        panel_height: usize,
+    ) -> Self {
+        self.orientation = orientation;
+        self.panel_width = panel_width;
+        self.panel_height = panel_height;
+        self
+    }
+
+    pub fn payload(&self) -> &PayloadType<'a> {
+        &self.payload
+    }
+
+    /// Delay between the frames of the animation payload, in milliseconds
+    pub fn animation_speed(&self) -> u16 {
+        self.animation_speed
+    }
+
+    pub fn font(&self) -> &Font<'a, G> {
+        &self.font
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Type of the packets sent, an image for the texts turned by the
+    /// orientation
+    pub fn content_type(&self) -> u8 {
+        if self.text_as_image() {
+            PacketType::Draw as u8
+        } else {
+            self.payload.get_content_type()
+        }
+    }
+
+    fn text_as_image(&self) -> bool {
+        matches!(self.payload, PayloadType::Text(_, _)) && !self.orientation.is_normal()
+    }
+
+    /// Characters of the text payload without a glyph in the font
+    pub fn missing_glyphs(&self) -> impl Iterator<Item = char> + '_ {
+        let text = match self.payload {
+            PayloadType::Text(phrase, _) => phrase,
+            _ => "",
+        };
+        self.font.missing_glyphs(text)
+    }
+
+    pub fn get_packets_count(&self) -> usize {
+        (self.get_total_bytes_from_phrase_data() + self.get_padding()).div_ceil(128)
+    }
+
+    /// Bytes of the payload data: the columns of the text, or the image and
+    /// animation planes
+    #[cfg(feature = "alloc")]
+    pub(crate) fn data_size(&self) -> usize {
+        self.get_total_bytes_from_phrase_data()
+    }
+
+    /// Write the payload data in `out`, returns [`Self::data_size`]
+    #[cfg(feature = "alloc")]
+    pub(crate) fn write_data(&self, out: &mut [u8]) -> usize {
+        self.write_payload_bytes(out, 0, self.data_size())
+    }
+
+    //Columns of the text payload, blank leading ones included
+    fn get_text_columns(&self, phrase: &str) -> usize {
+        phrase
+            .chars()
+            .map(|c| self.font.char_width(c) + self.font.style().gap)
+            .sum::<usize>()
+            + self.leading_columns(phrase)
+    }
+
+    //Blank columns before the text aligning it, measured with the current
+    //style and fallback
+    fn leading_columns(&self, phrase: &str) -> usize {
+        let (align, panel_width) = self.alignment;
+        align.offset(self.font.measure(phrase), panel_width)
+    }
+
+    fn get_total_bytes_from_phrase_data(&self) -> usize {
+        match self.payload {
+            PayloadType::Text(phrase,_) if self.text_as_image() => {
+                self.get_text_columns(phrase).max(self.panel_width) * 2 * 3
+            }
+            PayloadType::Text(phrase,_) => self.get_text_columns(phrase) * 2 * 3,
+            PayloadType::Image(data) => data.len(),
+            PayloadType::Animation(data,_) => data.len(),
         }
     }
 
@@ -94,17 +228,60 @@ impl<'a> CoolLEDWriter<'a> {
             missing_bytes
         };
 
-        match self.payload {
+        self.write_payload_bytes(out, skip_bytes, bytes_needed)
+    }
+
+    /// Write the bytes of the payload data from `skip`, turned by the
+    /// orientation
+    fn write_payload_bytes(&self, out: &mut [u8], skip: usize, bytes_needed: usize) -> usize {
+        let read = |out: &mut [u8], skip: usize, bytes_needed: usize| match self.payload {
             PayloadType::Text(phrase, colors) => {
-                self.write_bytes_from_phrase(out, skip_bytes, bytes_needed, phrase, colors)
+                self.write_bytes_from_phrase(out, skip, bytes_needed, phrase, colors)
             }
-            PayloadType::Image(image_data) => {
-                self.write_bytes_from_image(out, skip_bytes, bytes_needed, image_data)
+            PayloadType::Image(data) | PayloadType::Animation(data, _) => {
+                self.write_bytes_from_image(out, skip, bytes_needed, data)
             }
-            PayloadType::Animation(ani_data,_) => {
-                self.write_bytes_from_image(out, skip_bytes, bytes_needed, ani_data)
+        };
+        if self.orientation.is_normal() {
+            return read(out, skip, bytes_needed);
+        }
+
+        //Planes are turned column by column, read from the payload
+        let (height, frames) = match self.payload {
+            PayloadType::Text(_, _) => (TEXT_HEIGHT, 1),
+            PayloadType::Image(_) => (self.panel_height, 1),
+            PayloadType::Animation(_, frames) => (self.panel_height, frames.max(1)),
+        };
+        let column_size = height.div_ceil(8).clamp(1, MAX_COLUMN_SIZE);
+        let size = self.get_total_bytes_from_phrase_data();
+        let width = size / 3 / frames / column_size;
+        //The columns after the text are blank
+        let source_width = match self.payload {
+            PayloadType::Text(phrase, _) => self.get_text_columns(phrase),
+            _ => width,
+        };
+
+        let mut column = [0; MAX_COLUMN_SIZE];
+        let column = &mut column[..column_size];
+        let mut position = skip;
+        let end = (skip + bytes_needed).min(width * 3 * frames * column_size);
+        let mut bytes_wrote = 0;
+        while position < end {
+            let (index, byte) = (position / column_size, position % column_size);
+            let x = self.orientation.source_column(index % width, width);
+
+            column.fill(0);
+            if x < source_width {
+                read(column, ((index / width) * source_width + x) * column_size, column_size);
             }
+            self.orientation.transform_column(column, height);
+
+            let len = (column_size - byte).min(end - position);
+            out[bytes_wrote..bytes_wrote + len].copy_from_slice(&column[byte..byte + len]);
+            bytes_wrote += len;
+            position += len;
         }
+        bytes_wrote
     }
 
     fn write_bytes_from_image(
@@ -140,46 +317,32 @@ impl<'a> CoolLEDWriter<'a> {
         let len_chars = phrase.chars().count();
         let colors_len = colors.len();
 
-        let mut buff: [u8; 16] = [0; 16];
-
         let it = rgb_phrase.enumerate();
+        let leading_columns = self.leading_columns(phrase);
 
         let mut skip_needed = skip;
         let mut bytes_needed_count = bytes_needed;
-        let mut out_idx = 0;
-        for (idx, current_char) in it {
-            let char_size = self.get_font_byte_with_color(
-                current_char,
-                2,
-                idx,
-                len_chars,
-                colors[idx % colors_len],
-                &mut buff,
+        let mut emit = |column: [u8; 2]| {
+            bytes_wrote += copy_chunk(
+                &column,
+                &mut out[bytes_wrote..],
+                &mut skip_needed,
+                &mut bytes_needed_count,
             );
+        };
 
-            if skip_needed > char_size {
-                skip_needed -= char_size;
-            } else if skip_needed > 0 && skip_needed < char_size && bytes_needed_count > 0 {
-                let upper_bound = core::cmp::min(bytes_needed_count, char_size);
-                let range = skip_needed..upper_bound;
-                let range_len = range.len();
-                for out_byte_idx in range {
-                    out[out_idx] = buff[out_byte_idx];
-                    out_idx += 1;
-                    bytes_wrote += 1;
-                }
-                skip_needed = 0;
-                bytes_needed_count -= range_len;
-            } else if bytes_needed_count > 0 {
-                let upper_bound = core::cmp::min(bytes_needed_count, char_size);
-                for item in buff.iter().take(upper_bound) {
-                    out[out_idx] = *item;
-                    out_idx += 1;
-                    bytes_wrote += 1;
-                }
-
-                bytes_needed_count -= upper_bound;
+        for (idx, current_char) in it {
+            //Blank columns of the alignment, at the start of every color cycle
+            if idx % len_chars == 0 {
+                (0..leading_columns).for_each(|_| emit([0, 0]));
             }
+
+            //Columns are blank on the cycles of the colors the character lacks
+            let lit = colors[idx % colors_len].has(cycle_color(idx / len_chars));
+            for column in self.font.char_columns(current_char) {
+                emit(if lit { column } else { [0, 0] });
+            }
+            (0..self.font.style().gap).for_each(|_| emit([0, 0]));
         }
 
         bytes_wrote
@@ -203,7 +366,7 @@ impl<'a> CoolLEDWriter<'a> {
         self.write_bytes_from_phrase(&mut out[range_remaing.start..], 0, bytes_available, phrase, colors)
     }
 
-    fn make_image_payload(&self, out: &mut [u8], image_data: &[u8]) -> usize {
+    fn make_image_payload(&self, out: &mut [u8]) -> usize {
         let data_size = self.get_total_bytes_from_phrase_data();
         out[0] = data_size.shr(8) as u8;
         out[1] = (data_size & 0xff) as u8;
@@ -218,18 +381,12 @@ impl<'a> CoolLEDWriter<'a> {
             bytes_available
         );
 
-        self.write_bytes_from_image(
-            &mut out[range_remaing.start..],
-            0,
-            bytes_available,
-            image_data,
-        )
+        self.write_payload_bytes(&mut out[range_remaing.start..], 0, bytes_available)
     }
 
     fn make_animation_payload(
         &self,
         out: &mut [u8],
-        animation_data: &[u8],
         frames: usize,
         speed: usize,
     ) -> usize {
@@ -248,28 +405,29 @@ impl<'a> CoolLEDWriter<'a> {
             bytes_available
         );
 
-        self.write_bytes_from_image(
-            &mut out[range_remaing.start..],
-            0,
-            bytes_available,
-            animation_data,
-        )
+        self.write_payload_bytes(&mut out[range_remaing.start..], 0, bytes_available)
     }
 
     fn make_first_packet(&self, out: &mut [u8]) -> usize {
         out[0..24].fill(0x0);
 
         match self.payload {
+            PayloadType::Text(_,_) if self.text_as_image() => self.make_image_payload(&mut out[24..]),
             PayloadType::Text(phrase,colors) => self.make_text_payload(&mut out[24..], phrase, colors),
-            PayloadType::Image(image_data) => self.make_image_payload(&mut out[24..], image_data),
-            PayloadType::Animation(ani_data,frames_quantity) => {
-                self.make_animation_payload(&mut out[24..], ani_data, frames_quantity, 500)
+            PayloadType::Image(_) => self.make_image_payload(&mut out[24..]),
+            PayloadType::Animation(_,frames_quantity) => {
+                self.make_animation_payload(
+                    &mut out[24..],
+                    frames_quantity,
+                    self.animation_speed as usize,
+                )
             }
         }
     }
 
     fn get_padding(&self) -> usize {
         match self.payload {
+            PayloadType::Text(_,_) if self.text_as_image() => IMAGE_PREFIX_FIRST_PACKET_HEADER_SIZE,
             PayloadType::Text(_,_) => TEXT_PREFIX_FIRST_PACKET_HEADER_SIZE,
             PayloadType::Image(_) => IMAGE_PREFIX_FIRST_PACKET_HEADER_SIZE,
             PayloadType::Animation(_,_) => ANIMATION_PREFIX_FIRST_PACKET_HEADER_SIZE,
@@ -291,6 +449,8 @@ impl<'a> CoolLEDWriter<'a> {
         out[3] = idx.shr(8) as u8;
         out[4] = (idx & 0xff) as u8;
         out[5] = if idx == 0 {
+            //The first packet is always full, clear what the payload leaves
+            out[6..6 + 128].fill(0);
             self.make_first_packet(&mut out[6..6 + 128]);
             0x80
         } else {
@@ -307,7 +467,7 @@ impl<'a> CoolLEDWriter<'a> {
         out[1] = bytes_wrote.shr(8) as u8;
         out[2] = (bytes_wrote & 0xff) as u8;
         //Content type
-        out[3] = self.payload.get_content_type();
+        out[3] = self.content_type();
 
         let current_bytes_wrote = bytes_wrote;
 
@@ -317,14 +477,38 @@ impl<'a> CoolLEDWriter<'a> {
     }
 }
 
-#[cfg(all(test, not(feature = "custom_charset")))]
+/// Copy `chunk` to `out`, once `skip` bytes were skipped and while `needed`
+/// bytes are missing. Returns the number of bytes copied.
+fn copy_chunk(chunk: &[u8], out: &mut [u8], skip: &mut usize, needed: &mut usize) -> usize {
+    if *skip >= chunk.len() {
+        *skip -= chunk.len();
+        return 0;
+    }
+
+    let src = &chunk[*skip..];
+    let len = core::cmp::min(src.len(), *needed);
+    out[..len].copy_from_slice(&src[..len]);
+
+    *skip = 0;
+    *needed -= len;
+    len
+}
+
+#[cfg(test)]
 mod test {
     use super::*;
     extern crate alloc;
     extern crate std;
-    use alloc::vec;
+    use alloc::{vec, vec::Vec};
 
     #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    #[allow(
+        clippy::needless_borrow,
+        clippy::needless_range_loop,
+        clippy::explicit_counter_loop,
+        clippy::useless_vec
+    )]
     fn testing_hardcoded_packet_result() {
         const PHRASE: &str = "Testing";
         let colors: [CoolLEDColors; PHRASE.len()] = [CoolLEDColors::Red; PHRASE.len()];
@@ -386,4 +570,247 @@ mod test {
         }
         std::println!("|total : {}", total_written);
     }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn charset_matches_unicode_font() {
+        use crate::charset::{write_charset, Charset};
+
+        const PHRASE: &str = "Testing";
+        const LIST: &str = "Teginst";
+        let colors = [CoolLEDColors::Yellow, CoolLEDColors::Blue];
+
+        let glyphs: Vec<(char, &[u8; 32])> = LIST
+            .chars()
+            .map(|c| (c, UnicodeFont.glyph(c).unwrap().try_into().unwrap()))
+            .collect();
+        let mut charset = vec![];
+        write_charset(|byte| charset.push(byte), glyphs);
+
+        let mut unicode = CoolLEDWriter::new(PayloadType::Text(PHRASE, &colors));
+        let mut custom = CoolLEDWriter::with_glyphs(
+            PayloadType::Text(PHRASE, &colors),
+            Charset::parse(&charset).unwrap(),
+        );
+
+        assert_eq!(generate_all(&mut unicode), generate_all(&mut custom));
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn missing_glyphs_use_fallback() {
+        let colors = [CoolLEDColors::Green];
+
+        let writer = CoolLEDWriter::new(PayloadType::Text("a😀b😀\u{10ffff}", &colors))
+            .with_fallback(Fallback::Char('?'));
+        assert!(writer.missing_glyphs().eq(['😀', '\u{10ffff}']));
+
+        let mut with_fallback = writer;
+        let mut expected = CoolLEDWriter::new(PayloadType::Text("a?b??", &colors));
+        assert_eq!(generate_all(&mut with_fallback), generate_all(&mut expected));
+    }
+
+    #[test]
+    #[cfg(feature = "full-font")]
+    fn zero_width_chars_are_not_missing() {
+        //A blank glyph is still a glyph
+        let colors = [CoolLEDColors::Green];
+        let writer = CoolLEDWriter::new(PayloadType::Text("a\u{200b}b\u{200b}", &colors));
+        assert_eq!(writer.missing_glyphs().count(), 0);
+        assert_eq!(
+            writer.get_total_bytes_from_phrase_data(),
+            CoolLEDWriter::new(PayloadType::Text("a\u{200b}b", &colors))
+                .get_total_bytes_from_phrase_data()
+                + (8 + 1) * 2 * 3
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "cjk"))]
+    fn wide_and_blank_glyphs() {
+        let colors = [CoolLEDColors::Red];
+        let mut writer = CoolLEDWriter::new(PayloadType::Text("中 文", &colors));
+
+        assert_eq!(writer.missing_glyphs().count(), 0);
+        assert!(!generate_all(&mut writer).is_empty());
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn alignment_prepends_blank_columns() {
+        let colors = [CoolLEDColors::Red];
+        let writer = CoolLEDWriter::new(PayloadType::Text("Ti", &colors));
+        let left = writer.get_total_bytes_from_phrase_data();
+
+        let right = writer.with_alignment(Align::Right, 32);
+        assert_eq!(right.get_total_bytes_from_phrase_data(), left + 19 * 2 * 3);
+
+        let mut out = [0xffu8; 128];
+        let wrote = right.write_bytes_from_phrase(&mut out, 0, 128, "Ti", &colors);
+        assert_eq!(wrote, 128);
+        assert!(out[..38].iter().all(|byte| *byte == 0));
+        assert_eq!(&out[38..40], &[32, 0]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn alignment_follows_the_style() {
+        use crate::font::{Spacing, TextStyle};
+
+        let colors = [CoolLEDColors::Red];
+        let style = TextStyle {
+            spacing: Spacing::Monospace(8),
+            ..Default::default()
+        };
+        let mut aligned_first = CoolLEDWriter::new(PayloadType::Text("Ti", &colors))
+            .with_alignment(Align::Center, 32)
+            .with_style(style);
+        let mut styled_first = CoolLEDWriter::new(PayloadType::Text("Ti", &colors))
+            .with_style(style)
+            .with_alignment(Align::Center, 32);
+
+        //"Ti" is 17 columns wide once monospaced
+        assert_eq!(aligned_first.leading_columns("Ti"), 7);
+        assert_eq!(generate_all(&mut aligned_first), generate_all(&mut styled_first));
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn styled_text_size_matches_data() {
+        use crate::font::{Spacing, TextStyle};
+
+        const PHRASE: &str = "12:0 i";
+        let colors = [CoolLEDColors::Red, CoolLEDColors::Cyan];
+        let writer = CoolLEDWriter::new(PayloadType::Text(PHRASE, &colors)).with_style(TextStyle {
+            spacing: Spacing::Monospace(9),
+            gap: 3,
+            space_width: 2,
+        });
+
+        let total = writer.get_total_bytes_from_phrase_data();
+        assert_eq!(total, (5 * (9 + 3) + 2 + 3) * 2 * 3);
+
+        let mut out = vec![0xffu8; total + 16];
+        let wrote = writer.write_bytes_from_phrase(&mut out, 0, total, PHRASE, &colors);
+        assert_eq!(wrote, total);
+
+        //The red plane holds only the red characters, each plane is a third
+        let plane = total / 3;
+        let (red, rest) = out[..total].split_at(plane);
+        let (green, blue) = rest.split_at(plane);
+        assert_eq!(green, blue);
+        assert!(red[12 * 2..24 * 2].iter().all(|byte| *byte == 0));
+        assert!(green[..12 * 2].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn animation_header_has_frames_and_speed() {
+        let data = vec![0u8; 4 * 3 * 64];
+        //Header of the first packet once escaped
+        let header = |packet: &[u8], speed: u16| {
+            let [high, low] = speed.to_be_bytes();
+            let mut expected = vec![0; 24];
+            for byte in [4, high, low] {
+                crate::util::escpae_bytes(|escaped| expected.push(escaped), byte);
+            }
+            packet.windows(expected.len()).any(|window| window == expected)
+        };
+
+        let mut writer = CoolLEDWriter::new(PayloadType::Animation(&data, 4));
+        assert!(header(&generate_all(&mut writer)[0], DEFAULT_ANIMATION_SPEED));
+
+        let mut writer =
+            CoolLEDWriter::new(PayloadType::Animation(&data, 4)).with_animation_speed(1800);
+        assert!(header(&generate_all(&mut writer)[0], 1800));
+    }
+
+    #[test]
+    fn packets_in_a_reused_buffer() {
+        //Escaped data, and leftovers of a previous packet that would need
+        //escaping past the bytes written
+        let image = [0x01; 48];
+        let mut writer = CoolLEDWriter::new(PayloadType::Image(&image));
+        let expected = generate_all(&mut writer);
+
+        let mut buffer = [0x02; 255];
+        for (idx, packet) in expected.iter().enumerate() {
+            let wrote = writer.generate_packet(idx, &mut buffer);
+            assert_eq!(&buffer[..wrote], &packet[..]);
+        }
+    }
+
+    #[test]
+    fn orientation_turns_images_and_animations() {
+        //3 frames of 5 columns, 12 rows
+        let data: Vec<u8> = (0..3 * 3 * 5 * 2).map(|idx| (idx * 37) as u8).collect();
+        let orientation = Orientation {
+            mirror_horizontal: true,
+            mirror_vertical: true,
+            invert: true,
+        };
+        let mut expected = data.clone();
+        orientation.apply(&mut expected, 3, 12);
+
+        let writer =
+            CoolLEDWriter::new(PayloadType::Animation(&data, 3)).with_orientation(orientation, 5, 12);
+        assert_eq!(writer.content_type(), PacketType::Animate as u8);
+        let mut out = vec![0; data.len()];
+        assert_eq!(writer.write_payload_bytes(&mut out, 0, 200), data.len());
+        assert_eq!(out, expected);
+        //Packets start in the middle of columns
+        let mut part = [0; 7];
+        assert_eq!(writer.write_payload_bytes(&mut part, 5, 7), 7);
+        assert_eq!(part, expected[5..12]);
+
+        let image = &data[..30];
+        let writer = CoolLEDWriter::new(PayloadType::Image(image))
+            .with_orientation(Orientation::ROTATE_180, 5, 12);
+        let mut expected = image.to_vec();
+        Orientation::ROTATE_180.apply(&mut expected, 1, 12);
+        let mut out = vec![0; 30];
+        writer.write_payload_bytes(&mut out, 0, 30);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn turned_text_is_sent_as_an_image() {
+        let colors = [CoolLEDColors::Yellow];
+        let text = CoolLEDWriter::new(PayloadType::Text("Hi", &colors));
+        let size = text.get_total_bytes_from_phrase_data();
+        let mut planes = vec![0; size];
+        text.write_payload_bytes(&mut planes, 0, size);
+
+        //The image is padded to the panel width then turned
+        let columns = size / 6;
+        let mut expected = vec![0; 6 * 32];
+        for (plane, chunk) in planes.chunks(2 * columns).enumerate() {
+            expected[plane * 64..plane * 64 + chunk.len()].copy_from_slice(chunk);
+        }
+        Orientation::ROTATE_180.apply(&mut expected, 1, 16);
+
+        let mut turned = CoolLEDWriter::new(PayloadType::Text("Hi", &colors))
+            .with_orientation(Orientation::ROTATE_180, 32, 16);
+        assert_eq!(turned.content_type(), PacketType::Draw as u8);
+        assert_eq!(turned.get_total_bytes_from_phrase_data(), 6 * 32);
+        let mut out = vec![0; 6 * 32];
+        turned.write_payload_bytes(&mut out, 0, 6 * 32);
+        assert_eq!(out, expected);
+
+        //Sent with the header of an image
+        let mut image = CoolLEDWriter::new(PayloadType::Image(&expected));
+        assert_eq!(turned.get_packets_count(), image.get_packets_count());
+        assert_eq!(generate_all(&mut turned), generate_all(&mut image));
+    }
+
+    fn generate_all<G: GlyphProvider>(writer: &mut CoolLEDWriter<G>) -> Vec<Vec<u8>> {
+        let mut buffer: [u8; 255] = [0; 255];
+        (0..writer.get_packets_count())
+            .map(|idx| {
+                let wrote = writer.generate_packet(idx, &mut buffer);
+                let packet = buffer[..wrote].to_vec();
+                buffer.fill(0);
+                packet
+            })
+            .collect()
+    }
 }
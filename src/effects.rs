@@ -0,0 +1,237 @@
+//! Text effects the firmware does not have, rendered as animations.
+//!
+//! An effect pushes its frames to an [`AnimationBuilder`], so effects can be
+//! chained in one animation:
+//!
+//! ```
+//! use coolled1248::animation::AnimationBuilder;
+//! use coolled1248::canvas::Line;
+//! use coolled1248::colors::CoolLEDColors;
+//! use coolled1248::effects::TextEffect;
+//! use coolled1248::font::{Font, UnicodeFont};
+//! use coolled1248::layout::Align;
+//!
+//! let font = Font::new(UnicodeFont);
+//! let line = Line::new("Hi!", CoolLEDColors::Green).with_align(Align::Center);
+//! let mut builder = AnimationBuilder::<32, 16>::new().with_speed(100);
+//! TextEffect::WipeIn.push_frames(&mut builder, &font, &line)?;
+//! TextEffect::Blink { times: 3 }.push_frames(&mut builder, &font, &line)?;
+//! TextEffect::WipeOut.push_frames(&mut builder, &font, &line)?;
+//!
+//! let animation = builder.build()?;
+//! # Ok::<(), coolled1248::animation::AnimationError>(())
+//! ```
+
+use crate::animation::{check_limits, AnimationBuilder, AnimationError};
+use crate::canvas::{Canvas, Line};
+use crate::colors::{CoolLEDColors, PALETTE};
+use crate::font::{Font, GlyphProvider};
+
+/// Effect of a text animation. Texts are drawn from the top row and aligned
+/// as their [`Line`], they should fit in the panel except with
+/// [`TextEffect::Bounce`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEffect {
+    /// Characters appear one by one, from a blank panel to the whole text
+    Typewriter,
+    /// The text scrolls to its other end and back. A text narrower than the
+    /// panel moves between the panel sides.
+    Bounce,
+    /// The text is shown then hidden, `times` times
+    Blink { times: usize },
+    /// Every character in its own color, the colors shifting by one character
+    /// each frame through the 7 lit colors starting with the line color
+    ColorCycle,
+    /// The text is revealed column by column from the left
+    WipeIn,
+    /// The text is hidden column by column from the right
+    WipeOut,
+}
+
+impl TextEffect {
+    /// Push the frames of `line` with the effect to `builder`. Fails without
+    /// pushing a frame when the animation would exceed the limits of the
+    /// device.
+    pub fn push_frames<const W: usize, const H: usize, G: GlyphProvider>(
+        &self,
+        builder: &mut AnimationBuilder<W, H>,
+        font: &Font<'_, G>,
+        line: &Line<'_>,
+    ) -> Result<(), AnimationError> {
+        let count = self.frame_count::<W, G>(font, line);
+        check_limits(
+            builder.frames().saturating_add(count),
+            Canvas::<W, H>::PLANE_SIZE,
+        )?;
+        for idx in 0..count {
+            builder.push_frame(&self.frame(idx, font, line))?;
+        }
+        Ok(())
+    }
+
+    fn frame_count<const W: usize, G: GlyphProvider>(
+        &self,
+        font: &Font<'_, G>,
+        line: &Line<'_>,
+    ) -> usize {
+        let width = font.measure(line.text);
+        match self {
+            TextEffect::Typewriter => line.text.chars().count() + 1,
+            //To the other side and back, without showing the ends twice
+            TextEffect::Bounce => (2 * W.abs_diff(width)).max(1),
+            TextEffect::Blink { times } => times.saturating_mul(2),
+            TextEffect::ColorCycle => 7,
+            TextEffect::WipeIn | TextEffect::WipeOut => width + 1,
+        }
+    }
+
+    fn frame<const W: usize, const H: usize, G: GlyphProvider>(
+        &self,
+        idx: usize,
+        font: &Font<'_, G>,
+        line: &Line<'_>,
+    ) -> Canvas<W, H> {
+        let width = font.measure(line.text);
+        let x = line.align.offset(width, W) as i32;
+        let draw = |text: &str, x: i32, paint: &mut dyn FnMut(usize, i32) -> CoolLEDColors| {
+            let mut canvas = Canvas::new();
+            canvas.draw_text_with(font, text, x, 0, |idx, column, _| paint(idx, column));
+            canvas
+        };
+        let plain = |text: &str, x: i32| draw(text, x, &mut |_, _| line.color);
+        //Frame showing the columns before `end`
+        let wipe = |end: i32| {
+            draw(line.text, x, &mut |_, column| {
+                if column < end {
+                    line.color
+                } else {
+                    CoolLEDColors::Black
+                }
+            })
+        };
+
+        match self {
+            TextEffect::Typewriter => {
+                let end = line.text.char_indices().nth(idx).map(|(end, _)| end);
+                plain(&line.text[..end.unwrap_or(line.text.len())], x)
+            }
+            TextEffect::Bounce => {
+                //Scroll from the left side to the right one or the other way
+                let distance = W as i32 - width as i32;
+                let (step, end) = (distance.signum(), distance.abs());
+                let offset = if idx as i32 <= end {
+                    idx as i32
+                } else {
+                    2 * end - idx as i32
+                };
+                plain(line.text, offset * step)
+            }
+            TextEffect::Blink { .. } if idx % 2 == 1 => Canvas::new(),
+            TextEffect::Blink { .. } => plain(line.text, x),
+            TextEffect::ColorCycle => {
+                let lit = &PALETTE[..7];
+                let start = lit
+                    .iter()
+                    .position(|color| *color == line.color)
+                    .unwrap_or(0);
+                draw(line.text, x, &mut |char_idx, _| {
+                    lit[(start + idx + char_idx) % lit.len()]
+                })
+            }
+            TextEffect::WipeIn => wipe(x + idx as i32),
+            TextEffect::WipeOut => wipe(x + (width - idx) as i32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::small_font::{SmallFont, STYLE};
+    use alloc::vec::Vec;
+
+    fn frames(effect: TextEffect, line: &Line<'_>) -> Vec<Canvas<16, 8>> {
+        let font = Font::new(SmallFont).with_style(STYLE);
+        (0..effect.frame_count::<16, _>(&font, line))
+            .map(|idx| effect.frame(idx, &font, line))
+            .collect()
+    }
+
+    fn lit(canvas: &Canvas<16, 8>) -> usize {
+        (0..16)
+            .flat_map(|x| (0..8).map(move |y| (x, y)))
+            .filter(|(x, y)| canvas.pixel(*x, *y) != Some(CoolLEDColors::Black))
+            .count()
+    }
+
+    #[test]
+    fn typewriter_and_wipes() {
+        let line = Line::new("ab", CoolLEDColors::Red);
+        let typed = frames(TextEffect::Typewriter, &line);
+        assert_eq!(typed.len(), 3);
+        assert_eq!(lit(&typed[0]), 0);
+        assert!(lit(&typed[1]) > 0 && lit(&typed[1]) < lit(&typed[2]));
+
+        let wipe_in = frames(TextEffect::WipeIn, &line);
+        assert_eq!(wipe_in.len(), 12);
+        assert_eq!(lit(&wipe_in[0]), 0);
+        assert_eq!(wipe_in[11], typed[2]);
+        let mut wipe_out = frames(TextEffect::WipeOut, &line);
+        wipe_out.reverse();
+        assert_eq!(wipe_out, wipe_in);
+    }
+
+    #[test]
+    fn bounce_back_and_forth() {
+        //5 columns wide text on a 16 columns panel
+        let bounce = frames(TextEffect::Bounce, &Line::new("a", CoolLEDColors::Red));
+        assert_eq!(bounce.len(), 22);
+        let first_column = |canvas: &Canvas<16, 8>| {
+            (0..16).find(|x| (0..8).any(|y| canvas.pixel(*x, y) != Some(CoolLEDColors::Black)))
+        };
+        assert_eq!(first_column(&bounce[0]), Some(0));
+        assert_eq!(first_column(&bounce[11]), Some(11));
+        assert_eq!(first_column(&bounce[21]), Some(1));
+
+        //A text wider than the panel scrolls to its end
+        let wide = frames(TextEffect::Bounce, &Line::new("abc", CoolLEDColors::Red));
+        assert_eq!(wide.len(), 2);
+    }
+
+    #[test]
+    fn blink_and_color_cycle() {
+        let line = Line::new("ab", CoolLEDColors::Green);
+        let blink = frames(TextEffect::Blink { times: 2 }, &line);
+        assert_eq!(blink.len(), 4);
+        assert_eq!(lit(&blink[1]), 0);
+        assert_eq!(blink[0], blink[2]);
+
+        let cycle = frames(TextEffect::ColorCycle, &line);
+        assert_eq!(cycle.len(), 7);
+        let color = |canvas: &Canvas<16, 8>, x| {
+            (0..8).find_map(|y| canvas.pixel(x, y).filter(|c| *c != CoolLEDColors::Black))
+        };
+        //'a' then 'b' starting from the line color
+        assert_eq!(color(&cycle[0], 1), Some(CoolLEDColors::Green));
+        assert_eq!(color(&cycle[0], 7), Some(CoolLEDColors::Blue));
+        assert_eq!(color(&cycle[1], 1), Some(CoolLEDColors::Blue));
+    }
+
+    #[test]
+    fn limits_are_checked_first() {
+        let font = Font::new(crate::font::UnicodeFont);
+        let line = Line::new("a", CoolLEDColors::Red);
+        let mut builder = AnimationBuilder::<32, 16>::new();
+        assert_eq!(
+            TextEffect::Blink { times: 200 }.push_frames(&mut builder, &font, &line),
+            Err(AnimationError::TooManyFrames { frames: 400 })
+        );
+        assert_eq!(builder.frames(), 0);
+
+        //Rejected before rendering a frame
+        assert_eq!(
+            TextEffect::Blink { times: usize::MAX }.push_frames(&mut builder, &font, &line),
+            Err(AnimationError::TooManyFrames { frames: usize::MAX })
+        );
+    }
+}
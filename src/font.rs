@@ -1,94 +1,264 @@
+#[cfg(not(feature = "full-font"))]
+use crate::charset::Charset;
 use crate::colors::CoolLEDColors;
 use crate::coolled::CoolLEDWriter;
 
-#[cfg(not(feature = "custom_charset"))]
+#[cfg(feature = "full-font")]
 const FONT_DATA: &[u8; 2097152] = include_bytes!("../assets/font_data.bin");
 
+#[cfg(not(feature = "full-font"))]
+const FONT_SUBSET: Charset<'static> =
+    Charset::from_bytes_unchecked(include_bytes!(concat!(env!("OUT_DIR"), "/font_subset.bin")));
 
-impl<'a> CoolLEDWriter<'a> {
-    #[cfg(not(feature = "custom_charset"))]
-    //Read 32 bytes from the font data
-    fn read_font_bytes(&self, c: char) -> &'static [u8] {
-        &FONT_DATA[(c as u16 * ' ' as u16) as usize..(c as u16 * ' ' as u16 + 32) as usize]
+pub(crate) const EMPTY_GLYPH: [u8; 32] = [0; 32];
+
+/// Source of the 16x16 glyphs used to draw text.
+///
+/// A glyph is 32 bytes long: 16 columns of 2 bytes each, the most significant
+/// bit of the first byte being the top pixel of the column.
+pub trait GlyphProvider {
+    /// Return the 32 bytes of the glyph for `c`, or `None` when the font
+    /// has no glyph for it
+    fn glyph(&self, c: char) -> Option<&[u8]>;
+}
+
+impl<G: GlyphProvider + ?Sized> GlyphProvider for &G {
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        (**self).glyph(c)
     }
+}
 
-    #[cfg(feature = "custom_charset")]
+/// Glyphs from the bundled unicode font (`assets/font_data.bin`)
+///
+/// The font covers the Basic Multilingual Plane, the characters outside of it
+/// are missing. Without the `full-font` feature only the characters selected
+/// at build time are available.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnicodeFont;
+
+impl GlyphProvider for UnicodeFont {
+    #[cfg(feature = "full-font")]
     //Read 32 bytes from the font data
-    fn read_font_bytes(&self, character: char) -> &'a [u8] {
-        if let Some(pos) = self.custom_charset_list.chars().position(|needle_char| needle_char == character) {
-            let addr = pos * 0x20 as usize;
-            &self.custom_charset[addr..addr+0x20]
-        }else{
-            &self.custom_charset[0..0x20]
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        let addr = c as usize * 32;
+        FONT_DATA.get(addr..addr + 32)
+    }
+
+    #[cfg(not(feature = "full-font"))]
+    //Look up the charset generated by the build script
+    fn glyph(&self, c: char) -> Option<&[u8]> {
+        FONT_SUBSET.get(c)
+    }
+}
+
+/// What to draw for the characters missing in the font
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Fallback<'a> {
+    /// An empty glyph
+    #[default]
+    Blank,
+    /// The glyph of another character, or an empty glyph if it is missing too
+    Char(char),
+    /// A 32 bytes glyph
+    Glyph(&'a [u8; 32]),
+}
+
+/// Characters of `text` without a glyph in `glyphs`, each reported once
+#[cfg(feature = "alloc")]
+pub fn missing_glyphs<'t, G: GlyphProvider>(
+    glyphs: &'t G,
+    text: &'t str,
+) -> impl Iterator<Item = char> + 't {
+    let mut reported = alloc::collections::BTreeSet::new();
+    text.chars()
+        .filter(|c| glyphs.glyph(*c).is_none())
+        .filter(move |c| reported.insert(*c))
+}
+
+/// Characters of `text` without a glyph in `glyphs`, each reported once
+#[cfg(not(feature = "alloc"))]
+pub fn missing_glyphs<'t, G: GlyphProvider>(
+    glyphs: &'t G,
+    text: &'t str,
+) -> impl Iterator<Item = char> + 't {
+    //Without a set, only the missing characters are looked up before them
+    text.char_indices()
+        .filter(|(_, c)| glyphs.glyph(*c).is_none())
+        .filter(|(idx, c)| !text[..*idx].contains(*c))
+        .map(|(_, c)| c)
+}
+
+/// Width given to each character
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Spacing {
+    /// Characters are trimmed to their drawn columns
+    #[default]
+    Proportional,
+    /// Every character is centered in the given number of columns, wider ones
+    /// are cropped
+    Monospace(usize),
+}
+
+/// How the glyphs are laid out in a line of text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextStyle {
+    pub spacing: Spacing,
+    /// Blank columns after each character
+    pub gap: usize,
+    /// Columns of the characters without any drawing, like spaces
+    pub space_width: usize,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            spacing: Spacing::Proportional,
+            gap: 1,
+            space_width: 8,
+        }
+    }
+}
+
+/// Glyphs used to draw text, with the fallback for the characters they miss
+/// and the style used to lay them out
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Font<'a, G = UnicodeFont> {
+    glyphs: G,
+    fallback: Fallback<'a>,
+    style: TextStyle,
+}
+
+impl<'a, G: GlyphProvider> Font<'a, G> {
+    pub fn new(glyphs: G) -> Self {
+        Self {
+            glyphs,
+            fallback: Fallback::Blank,
+            style: TextStyle::default(),
         }
     }
 
-    fn delete_empty_column(data: &[u8], out: &mut [u8]) -> usize {
-        let first_non_zero_idx_inc = {
-            let mut idxf = None;
-            for current_idx in (0..31).step_by(2) {
-                if data[current_idx] != 0 || data[current_idx + 1] != 0 {
-                    idxf = Some(current_idx);
-                    break;
-                }
+    /// Set what is drawn for the characters missing in the glyphs
+    pub fn with_fallback(mut self, fallback: Fallback<'a>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    pub(crate) fn set_fallback(&mut self, fallback: Fallback<'a>) {
+        self.fallback = fallback;
+    }
+
+    /// Set how the characters are laid out
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub(crate) fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+    }
+
+    pub fn glyphs(&self) -> &G {
+        &self.glyphs
+    }
+
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    /// Characters of `text` without a glyph, each reported once
+    pub fn missing_glyphs<'t>(&'t self, text: &'t str) -> impl Iterator<Item = char> + 't {
+        missing_glyphs(&self.glyphs, text)
+    }
+
+    /// Return the 32 bytes drawn for `c`
+    pub fn glyph(&self, c: char) -> &[u8] {
+        self.glyphs.glyph(c).unwrap_or_else(|| match self.fallback {
+            Fallback::Blank => &EMPTY_GLYPH,
+            Fallback::Char(fallback) => self.glyphs.glyph(fallback).unwrap_or(&EMPTY_GLYPH),
+            Fallback::Glyph(glyph) => glyph,
+        })
+    }
+
+    /// Width in columns of `c`, without the gap following it
+    pub fn char_width(&self, c: char) -> usize {
+        self.char_layout(c).2
+    }
+
+    /// Columns of `c` (2 bytes each, top pixel in the most significant bit),
+    /// without the gap following it
+    pub fn char_columns(&self, c: char) -> impl Iterator<Item = [u8; 2]> + '_ {
+        let (glyph, start, width) = self.char_layout(c);
+
+        (start..start + width as isize).map(move |column| {
+            if (0..16).contains(&column) {
+                let addr = column as usize * 2;
+                [glyph[addr], glyph[addr + 1]]
+            } else {
+                [0, 0]
             }
-            idxf
+        })
+    }
+
+    /// Width in columns of `text` as drawn on the panel, without the gap
+    /// following the last character
+    pub fn measure(&self, text: &str) -> usize {
+        let columns: usize = text
+            .chars()
+            .map(|c| self.char_width(c) + self.style.gap)
+            .sum();
+        columns.saturating_sub(self.style.gap)
+    }
+
+    //Glyph of the character, its first column (may be outside of the glyph
+    //for the padding of monospaced characters) and its width
+    fn char_layout(&self, c: char) -> (&[u8], isize, usize) {
+        let glyph = self.glyph(c);
+        let inked = |column: &usize| glyph[column * 2] != 0 || glyph[column * 2 + 1] != 0;
+
+        let (first, last) = match ((0..16).find(inked), (0..16).rev().find(inked)) {
+            (Some(first), Some(last)) => (first as isize, last as isize),
+            _ => return (glyph, 0, self.style.space_width),
         };
+        let inked_width = last - first + 1;
 
-        if let Some(first_non_zero_idx_inc) = first_non_zero_idx_inc {
-            let first_non_zero_idx_dec = {
-                let mut idxf = None;
-                for current_rev in (0..31).step_by(2).rev() {
-                    if data[current_rev] != 0 || data[current_rev + 1] != 0 {
-                        idxf = Some(current_rev);
-                        break;
-                    }
-                }
-                idxf
-            };
-
-            if let Some(first_non_zero_idx_dec) = first_non_zero_idx_dec {
-                match first_non_zero_idx_inc.cmp(&first_non_zero_idx_dec) {
-                    core::cmp::Ordering::Less => {
-                        let src = &data[first_non_zero_idx_inc..first_non_zero_idx_dec + 2];
-                        out[0..src.len()].clone_from_slice(src);
-                        return src.len();
-                    }
-                    core::cmp::Ordering::Equal => {
-                        let src = &data[first_non_zero_idx_inc..first_non_zero_idx_inc + 2];
-                        out[0..src.len()].clone_from_slice(src);
-                        return src.len();
-                    }
-                    core::cmp::Ordering::Greater => {
-                        out[0..16].fill(0);
-                        return out[0..16].len();
-                    }
-                }
+        match self.style.spacing {
+            Spacing::Proportional => (glyph, first, inked_width as usize),
+            Spacing::Monospace(width) => {
+                let start = first + (inked_width - width as isize) / 2;
+                (glyph, start, width)
             }
         }
-
-        out[0..16].fill(0);
-        out[0..16].len()
     }
 
+    /// Write the columns of `character` in `out`, followed by the gap when `i`
+    /// is 2 or 3. Returns the number of bytes written.
     pub fn get_font_byte_trimmed(&self, character: char, i: i32, out: &mut [u8]) -> usize {
-        let unicode_data = self.read_font_bytes(character);
+        let mut size = 0;
+        for column in self.char_columns(character) {
+            out[size..size + 2].copy_from_slice(&column);
+            size += 2;
+        }
 
         if i == 2 || i == 3 {
-            let data_size = Self::delete_empty_column(unicode_data, out);
-            out[data_size] = 0;
-            out[data_size + 1] = 0;
-            out[0..=data_size + 1].len()
-        } else {
-            Self::delete_empty_column(unicode_data, out)
+            let gap = self.style.gap * 2;
+            out[size..size + gap].fill(0);
+            size += gap;
         }
+
+        size
+    }
+}
+
+impl<'a, G: GlyphProvider> CoolLEDWriter<'a, G> {
+    pub fn get_font_byte_trimmed(&self, character: char, i: i32, out: &mut [u8]) -> usize {
+        self.font.get_font_byte_trimmed(character, i, out)
     }
 
     //We write the bytes of the character if the character color
     //needs the current cycle color.
     //We cycle between RGB.
     //i.e if the character color is yellow, we need red and green, and
-    //we skip blue writing 16x 0x00
+    //we skip blue writing 0x00 for the whole width of the character
     pub fn get_font_byte_with_color(
         &self,
         c: char,
@@ -102,17 +272,54 @@ impl<'a> CoolLEDWriter<'a> {
 
         let current_char_size = self.get_font_byte_trimmed(c, i, out);
 
-        let cycle_color = match current_cycle {
-            1 => CoolLEDColors::Green,
-            2 => CoolLEDColors::Blue,
-            _ => CoolLEDColors::Red,
-        };
-        if character_color.has(cycle_color) {
-            current_char_size
-        } else {
-            let dst = &mut out[0..16];
-            dst.fill(0);
-            dst.len()
+        if !character_color.has(cycle_color(current_cycle)) {
+            out[0..current_char_size].fill(0);
         }
+        current_char_size
+    }
+}
+
+/// Color plane written on the given cycle of the text payload
+pub(crate) fn cycle_color(cycle: usize) -> CoolLEDColors {
+    match cycle {
+        1 => CoolLEDColors::Green,
+        2 => CoolLEDColors::Blue,
+        _ => CoolLEDColors::Red,
+    }
+}
+
+#[cfg(all(test, any(feature = "full-font", feature = "latin")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn monospace_digits_have_the_same_width() {
+        let font = Font::new(UnicodeFont).with_style(TextStyle {
+            spacing: Spacing::Monospace(8),
+            gap: 2,
+            space_width: 4,
+        });
+
+        assert!("0123456789".chars().all(|c| font.char_width(c) == 8));
+        assert_eq!(font.char_width(' '), 4);
+        assert_eq!(font.measure("12:34"), 5 * 8 + 4 * 2);
+        assert_eq!(font.char_columns('1').count(), 8);
+
+        //Inked columns are centered, 'i' is 5 columns wide
+        let columns: [[u8; 2]; 8] =
+            core::array::from_fn(|idx| font.char_columns('i').nth(idx).unwrap());
+        assert_eq!(columns[0], [0, 0]);
+        assert_ne!(columns[1], [0, 0]);
+        assert_ne!(columns[5], [0, 0]);
+        assert_eq!(columns[6], [0, 0]);
+    }
+
+    #[test]
+    fn proportional_trims_glyphs() {
+        let font = Font::new(UnicodeFont);
+
+        assert_eq!(font.char_width('T'), 7);
+        assert_eq!(font.char_width(' '), 8);
+        assert_eq!(font.char_columns('T').next(), Some([32, 0]));
     }
 }
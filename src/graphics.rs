@@ -0,0 +1,103 @@
+//! [`embedded-graphics`](https://docs.rs/embedded-graphics) support.
+//!
+//! [`Canvas`] is a `DrawTarget` of [`CoolLEDColors`], `Rgb888` and
+//! `BinaryColor` convert into the closest color of the palette. To draw with
+//! them use `canvas.color_converted()` from `DrawTargetExt`.
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{BinaryColor, PixelColor, Rgb888, RgbColor},
+    Pixel,
+};
+
+use crate::canvas::Canvas;
+use crate::colors::CoolLEDColors;
+
+impl PixelColor for CoolLEDColors {
+    type Raw = ();
+}
+
+impl From<Rgb888> for CoolLEDColors {
+    fn from(color: Rgb888) -> Self {
+        CoolLEDColors::from_rgb([color.r(), color.g(), color.b()])
+    }
+}
+
+impl From<CoolLEDColors> for Rgb888 {
+    fn from(color: CoolLEDColors) -> Self {
+        let [red, green, blue] = color.to_rgb();
+        Rgb888::new(red, green, blue)
+    }
+}
+
+impl From<BinaryColor> for CoolLEDColors {
+    fn from(color: BinaryColor) -> Self {
+        match color {
+            BinaryColor::On => CoolLEDColors::White,
+            BinaryColor::Off => CoolLEDColors::Black,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> OriginDimensions for Canvas<W, H> {
+    fn size(&self) -> Size {
+        Size::new(W as u32, H as u32)
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for Canvas<W, H> {
+    type Color = CoolLEDColors;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point.x, point.y, color);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(color);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use embedded_graphics_core::{geometry::Point, primitives::Rectangle};
+
+    #[test]
+    fn draw_on_canvas() {
+        let mut canvas = Canvas::<8, 8>::new();
+        assert_eq!(canvas.size(), Size::new(8, 8));
+
+        let pixels = [
+            Pixel(Point::new(0, 0), Rgb888::new(250, 10, 0).into()),
+            Pixel(Point::new(1, 0), BinaryColor::On.into()),
+            Pixel(Point::new(-1, 9), CoolLEDColors::Blue),
+        ];
+        canvas.draw_iter(pixels).unwrap();
+        assert_eq!(canvas.pixel(0, 0), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(1, 0), Some(CoolLEDColors::White));
+
+        let area = Rectangle::new(Point::new(6, 6), Size::new(4, 4));
+        canvas.fill_solid(&area, CoolLEDColors::Cyan).unwrap();
+        assert_eq!(canvas.pixel(7, 7), Some(CoolLEDColors::Cyan));
+        assert_eq!(canvas.pixel(5, 7), Some(CoolLEDColors::Black));
+    }
+
+    #[test]
+    fn rgb_conversions() {
+        for color in crate::colors::PALETTE {
+            assert_eq!(CoolLEDColors::from(Rgb888::from(color)), color);
+        }
+        assert_eq!(CoolLEDColors::from(BinaryColor::Off), CoolLEDColors::Black);
+    }
+}
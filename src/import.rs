@@ -0,0 +1,585 @@
+//! Conversion of pictures decoded by the [`image`] crate into payloads.
+//!
+//! Pictures of any size and pixel format are first composited over a
+//! background color and fitted to the panel following a [`Scaling`] mode.
+//! The panel only shows the 8 colors of [`PALETTE`](crate::colors::PALETTE),
+//! pixels are then quantized to them with one of the [`Dithering`] methods
+//! after their brightness and gamma are adjusted.
+//!
+//! GIF and APNG animations are imported with [`gif_to_animation`] and
+//! [`apng_to_animation`]. The device plays every frame for the same time, the
+//! frames are repeated or dropped to keep the timing of the source.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use std::io::{BufRead, Read, Seek};
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+pub use image::imageops::FilterType;
+use image::{imageops, AnimationDecoder, DynamicImage, Frames, ImageError, Rgb, RgbImage};
+
+use crate::animation::{assemble, check_limits, Animation, AnimationError};
+use crate::canvas::{pack_planes, plane_size, Canvas};
+use crate::colors::CoolLEDColors;
+
+/// Error returned when importing an animation
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file could not be decoded
+    Decode(ImageError),
+    /// The frames do not make an animation the device can play
+    Animation(AnimationError),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Decode(error) => write!(f, "cannot decode the animation: {}", error),
+            ImportError::Animation(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Decode(error) => Some(error),
+            ImportError::Animation(error) => Some(error),
+        }
+    }
+}
+
+impl From<ImageError> for ImportError {
+    fn from(error: ImageError) -> Self {
+        ImportError::Decode(error)
+    }
+}
+
+impl From<AnimationError> for ImportError {
+    fn from(error: AnimationError) -> Self {
+        ImportError::Animation(error)
+    }
+}
+
+/// How pixels are quantized to the colors of the panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dithering {
+    /// Light a channel when its value is at least the given one
+    Threshold(u8),
+    /// Closest color of the palette
+    #[default]
+    Nearest,
+    /// Floyd–Steinberg error diffusion, for photos and gradients
+    FloydSteinberg,
+    /// Ordered dithering with a 4x4 Bayer matrix, stable across the frames of
+    /// an animation
+    Ordered,
+}
+
+/// How a picture is fitted to the panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scaling {
+    /// Scale to fit the panel keeping the aspect ratio, centered on the
+    /// background
+    #[default]
+    Fit,
+    /// Scale to cover the panel keeping the aspect ratio, centered and cut
+    Fill,
+    /// Scale to the size of the panel, ignoring the aspect ratio
+    Stretch,
+    /// Keep the size, centered on the panel
+    Center,
+    /// Keep the size, the top left corner on the panel
+    Crop,
+}
+
+/// Settings of the conversion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImportOptions {
+    pub scaling: Scaling,
+    /// Resampling filter used to scale the picture
+    pub filter: FilterType,
+    /// Color behind transparent pixels and around the picture
+    pub background: [u8; 3],
+    pub dithering: Dithering,
+    /// Factor applied to every channel after the gamma, 1 keeps them as is
+    pub brightness: f32,
+    /// Exponent applied to every channel, above 1 darkens the midtones
+    pub gamma: f32,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            scaling: Scaling::default(),
+            filter: FilterType::Triangle,
+            background: [0; 3],
+            dithering: Dithering::default(),
+            brightness: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl ImportOptions {
+    //Lookup table of the brightness and gamma adjustments
+    fn levels(&self) -> [u8; 256] {
+        let mut levels = [0; 256];
+        for (value, level) in levels.iter_mut().enumerate() {
+            let adjusted = (value as f32 / 255.0).powf(self.gamma) * self.brightness * 255.0;
+            *level = adjusted.round().clamp(0.0, 255.0) as u8;
+        }
+        levels
+    }
+}
+
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Colors of the pixels of `image`, row by row
+pub fn quantize(image: &RgbImage, options: &ImportOptions) -> Vec<CoolLEDColors> {
+    let levels = options.levels();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut pixels: Vec<[f32; 3]> = image
+        .pixels()
+        .map(|Rgb(rgb)| rgb.map(|value| levels[value as usize] as f32))
+        .collect();
+
+    let to_u8 = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    let nearest = |rgb: [f32; 3]| CoolLEDColors::from_rgb(rgb.map(to_u8));
+
+    let mut colors = Vec::with_capacity(pixels.len());
+    for y in 0..height {
+        for x in 0..width {
+            let rgb = pixels[y * width + x];
+            let color = match options.dithering {
+                Dithering::Threshold(threshold) => {
+                    let [red, green, blue] = rgb.map(|value| to_u8(value) >= threshold);
+                    CoolLEDColors::from_planes(red, green, blue)
+                }
+                Dithering::Nearest => nearest(rgb),
+                Dithering::FloydSteinberg => {
+                    let color = nearest(rgb);
+                    let lit = color.to_rgb();
+                    let error: [f32; 3] = core::array::from_fn(|c| rgb[c] - lit[c] as f32);
+
+                    let mut spread = |dx: isize, dy: usize, weight: f32| {
+                        let nx = x as isize + dx;
+                        if nx >= 0 && (nx as usize) < width && y + dy < height {
+                            let pixel = &mut pixels[(y + dy) * width + nx as usize];
+                            for c in 0..3 {
+                                pixel[c] += error[c] * weight / 16.0;
+                            }
+                        }
+                    };
+                    spread(1, 0, 7.0);
+                    spread(-1, 1, 3.0);
+                    spread(0, 1, 5.0);
+                    spread(1, 1, 1.0);
+                    color
+                }
+                Dithering::Ordered => {
+                    let threshold = (BAYER[y % 4][x % 4] as f32 + 0.5) * 255.0 / 16.0;
+                    let [red, green, blue] = rgb.map(|value| value > threshold);
+                    CoolLEDColors::from_planes(red, green, blue)
+                }
+            };
+            colors.push(color);
+        }
+    }
+    colors
+}
+
+/// Payload of a `PayloadType::Image` for a panel of `width` by `height`
+/// pixels showing `image`
+pub fn image_to_payload(
+    image: &DynamicImage,
+    width: usize,
+    height: usize,
+    options: &ImportOptions,
+) -> Vec<u8> {
+    let colors = quantize(&fit(image, width, height, options), options);
+    let mut payload = vec![0; 3 * plane_size(width, height)];
+    pack_planes(width, height, |x, y| colors[y * width + x], &mut payload);
+    payload
+}
+
+/// Canvas showing `image`
+pub fn image_to_canvas<const W: usize, const H: usize>(
+    image: &DynamicImage,
+    options: &ImportOptions,
+) -> Canvas<W, H> {
+    let colors = quantize(&fit(image, W, H, options), options);
+    let mut canvas = Canvas::new();
+    for (idx, color) in colors.into_iter().enumerate() {
+        canvas.set_pixel((idx % W) as i32, (idx / W) as i32, color);
+    }
+    canvas
+}
+
+/// Import a GIF animation for a panel of `width` by `height` pixels, playing
+/// a frame every `speed` milliseconds
+pub fn gif_to_animation<R: Read>(
+    reader: R,
+    width: usize,
+    height: usize,
+    speed: u16,
+    options: &ImportOptions,
+) -> Result<Animation, ImportError> {
+    let frames = GifDecoder::new(reader)?.into_frames();
+    frames_to_animation(frames, width, height, speed, options)
+}
+
+/// Import an APNG animation for a panel of `width` by `height` pixels,
+/// playing a frame every `speed` milliseconds. A still PNG gives a single
+/// frame.
+pub fn apng_to_animation<R: BufRead + Seek>(
+    reader: R,
+    width: usize,
+    height: usize,
+    speed: u16,
+    options: &ImportOptions,
+) -> Result<Animation, ImportError> {
+    let decoder = PngDecoder::new(reader)?;
+    if !decoder.is_apng() {
+        let image = DynamicImage::from_decoder(decoder)?;
+        let frame = image::Frame::new(image.to_rgba8());
+        let frames = Frames::new(alloc::boxed::Box::new(core::iter::once(Ok(frame))));
+        return frames_to_animation(frames, width, height, speed, options);
+    }
+    frames_to_animation(decoder.apng().into_frames(), width, height, speed, options)
+}
+
+/// Import decoded frames for a panel of `width` by `height` pixels, playing a
+/// frame every `speed` milliseconds. Stops at the first frame going over the
+/// limits of the device, without decoding the next ones.
+pub fn frames_to_animation(
+    frames: Frames<'_>,
+    width: usize,
+    height: usize,
+    speed: u16,
+    options: &ImportOptions,
+) -> Result<Animation, ImportError> {
+    if speed == 0 {
+        return Err(AnimationError::InvalidSpeed.into());
+    }
+
+    //Planes of every source frame and the time it ends at
+    let speed_ms = speed as u64;
+    let mut sources = Vec::new();
+    let mut duration = 0;
+    for frame in frames {
+        let frame = frame?;
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        let delay = numerator.checked_div(denominator).unwrap_or(0) as u64;
+        //Like browsers, play the frames without a delay for 100ms
+        duration += if delay <= 10 { 100 } else { delay };
+        let count = ((duration + speed_ms / 2) / speed_ms) as usize;
+        check_limits(count, plane_size(width, height))?;
+
+        let image = DynamicImage::ImageRgba8(frame.into_buffer());
+        sources.push((image_to_payload(&image, width, height, options), duration));
+    }
+    if sources.is_empty() {
+        return Err(AnimationError::Empty.into());
+    }
+
+    //Count the device frames showing each source frame, in their middle
+    let count = ((duration + speed_ms / 2) / speed_ms).max(1);
+    let mut times = vec![0; sources.len()];
+    for idx in 0..count {
+        let time = (idx * speed_ms + speed_ms / 2).min(duration - 1);
+        let source = sources
+            .iter()
+            .position(|(_, end)| time < *end)
+            .unwrap_or(sources.len() - 1);
+        times[source] += 1;
+    }
+
+    let frames = sources
+        .iter()
+        .zip(times.iter())
+        .map(|((planes, _), times)| (planes.as_slice(), *times));
+    Ok(assemble(frames, plane_size(width, height), speed)?)
+}
+
+/// `image` composited over the background and fitted to a panel of `width`
+/// by `height` pixels
+pub fn fit(image: &DynamicImage, width: usize, height: usize, options: &ImportOptions) -> RgbImage {
+    let (width, height) = (width as u32, height as u32);
+    let background = Rgb(options.background);
+
+    let rgba = image.to_rgba8();
+    let opaque = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [red, green, blue, alpha] = rgba.get_pixel(x, y).0;
+        let blend = |value: u8, under: u8| {
+            ((value as u32 * alpha as u32 + under as u32 * (255 - alpha as u32) + 127) / 255) as u8
+        };
+        let [under_red, under_green, under_blue] = options.background;
+        Rgb([
+            blend(red, under_red),
+            blend(green, under_green),
+            blend(blue, under_blue),
+        ])
+    });
+    if opaque.width() == 0 || opaque.height() == 0 {
+        return RgbImage::from_pixel(width, height, background);
+    }
+
+    let scale = |cover: bool| {
+        let ratio_x = width as f32 / opaque.width() as f32;
+        let ratio_y = height as f32 / opaque.height() as f32;
+        let ratio = if cover {
+            ratio_x.max(ratio_y)
+        } else {
+            ratio_x.min(ratio_y)
+        };
+        let scaled = |size: u32| ((size as f32 * ratio).round() as u32).max(1);
+        imageops::resize(
+            &opaque,
+            scaled(opaque.width()),
+            scaled(opaque.height()),
+            options.filter,
+        )
+    };
+    let scaled = match options.scaling {
+        Scaling::Fit => scale(false),
+        Scaling::Fill => scale(true),
+        Scaling::Stretch => imageops::resize(&opaque, width, height, options.filter),
+        Scaling::Center | Scaling::Crop => opaque,
+    };
+
+    let (left, top) = match options.scaling {
+        Scaling::Crop => (0, 0),
+        _ => (
+            (width as i64 - scaled.width() as i64) / 2,
+            (height as i64 - scaled.height() as i64) / 2,
+        ),
+    };
+    RgbImage::from_fn(width, height, |x, y| {
+        let (sx, sy) = (x as i64 - left, y as i64 - top);
+        if (0..scaled.width() as i64).contains(&sx) && (0..scaled.height() as i64).contains(&sy) {
+            *scaled.get_pixel(sx as u32, sy as u32)
+        } else {
+            background
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, ImageBuffer, Luma, Rgba, RgbaImage};
+
+    fn gray(width: u32, height: u32, value: u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([value; 3])))
+    }
+
+    fn lit(colors: &[CoolLEDColors]) -> usize {
+        colors
+            .iter()
+            .filter(|c| **c == CoolLEDColors::White)
+            .count()
+    }
+
+    #[test]
+    fn threshold_and_nearest() {
+        let image = RgbImage::from_fn(2, 1, |x, _| Rgb([200, 100, if x == 0 { 0 } else { 255 }]));
+
+        let nearest = quantize(&image, &ImportOptions::default());
+        assert_eq!(nearest, [CoolLEDColors::Red, CoolLEDColors::Pink]);
+
+        let options = ImportOptions {
+            dithering: Dithering::Threshold(255),
+            ..Default::default()
+        };
+        assert_eq!(
+            quantize(&image, &options),
+            [CoolLEDColors::Black, CoolLEDColors::Blue]
+        );
+    }
+
+    #[test]
+    fn dithering_keeps_the_average() {
+        let image = gray(16, 16, 128).to_rgb8();
+        assert_eq!(lit(&quantize(&image, &ImportOptions::default())), 256);
+
+        let ordered = ImportOptions {
+            dithering: Dithering::Ordered,
+            ..Default::default()
+        };
+        assert_eq!(lit(&quantize(&image, &ordered)), 128);
+
+        let diffused = ImportOptions {
+            dithering: Dithering::FloydSteinberg,
+            ..Default::default()
+        };
+        let count = lit(&quantize(&image, &diffused));
+        assert!((120..=136).contains(&count), "{} pixels lit", count);
+    }
+
+    #[test]
+    fn brightness_and_gamma() {
+        let image = gray(1, 1, 160).to_rgb8();
+        let dark = ImportOptions {
+            gamma: 2.2,
+            ..Default::default()
+        };
+        assert_eq!(quantize(&image, &dark), [CoolLEDColors::Black]);
+
+        let off = ImportOptions {
+            brightness: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            quantize(&gray(1, 1, 255).to_rgb8(), &off),
+            [CoolLEDColors::Black]
+        );
+    }
+
+    fn lit_rows(image: &RgbImage, x: u32) -> Vec<u32> {
+        (0..image.height())
+            .filter(|y| image.get_pixel(x, *y).0 != [0; 3])
+            .collect()
+    }
+
+    #[test]
+    fn scaling_modes() {
+        let wide = gray(64, 16, 255);
+        let with = |scaling| ImportOptions {
+            scaling,
+            ..Default::default()
+        };
+
+        let fitted = fit(&wide, 32, 16, &with(Scaling::Fit));
+        assert_eq!(fitted.dimensions(), (32, 16));
+        assert_eq!(lit_rows(&fitted, 0), (4..12).collect::<Vec<_>>());
+
+        let filled = fit(&wide, 32, 16, &with(Scaling::Fill));
+        assert_eq!(lit_rows(&filled, 0).len(), 16);
+        let stretched = fit(&gray(4, 4, 255), 32, 16, &with(Scaling::Stretch));
+        assert!(stretched.pixels().all(|pixel| pixel.0 == [255; 3]));
+
+        let small = gray(8, 8, 255);
+        let centered = fit(&small, 32, 16, &with(Scaling::Center));
+        assert_eq!(lit_rows(&centered, 11), []);
+        assert_eq!(lit_rows(&centered, 12), (4..12).collect::<Vec<_>>());
+        let cropped = fit(&small, 32, 16, &with(Scaling::Crop));
+        assert_eq!(lit_rows(&cropped, 0), (0..8).collect::<Vec<_>>());
+        assert_eq!(lit_rows(&cropped, 8), []);
+    }
+
+    #[test]
+    fn any_pixel_format_over_background() {
+        let options = ImportOptions {
+            scaling: Scaling::Crop,
+            background: [0, 0, 255],
+            ..Default::default()
+        };
+
+        let transparent = DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 1, |x, _| {
+            Rgba([255, 0, 0, if x == 0 { 0 } else { 255 }])
+        }));
+        let pixels = quantize(&fit(&transparent, 3, 1, &options), &options);
+        assert_eq!(
+            pixels,
+            [CoolLEDColors::Blue, CoolLEDColors::Red, CoolLEDColors::Blue]
+        );
+
+        let luma = DynamicImage::ImageLuma16(ImageBuffer::from_pixel(1, 1, Luma([0xffffu16])));
+        let pixels = quantize(&fit(&luma, 1, 1, &options), &options);
+        assert_eq!(pixels, [CoolLEDColors::White]);
+    }
+
+    #[test]
+    fn payload_is_cropped_to_the_panel() {
+        let options = ImportOptions {
+            scaling: Scaling::Crop,
+            ..Default::default()
+        };
+        let payload = image_to_payload(&gray(40, 8, 255), 32, 16, &options);
+        assert_eq!(payload.len(), 3 * 64);
+        assert!(payload.chunks(2).all(|column| column == [0xff, 0x00]));
+
+        let canvas = image_to_canvas::<32, 16>(&gray(40, 8, 255), &options);
+        let mut planes = [0; Canvas::<32, 16>::SIZE];
+        canvas.write_planes(&mut planes);
+        assert_eq!(planes[..], payload[..]);
+    }
+
+    fn gif(frames: &[([u8; 4], u32)]) -> Vec<u8> {
+        let mut gif = Vec::new();
+        let mut encoder = GifEncoder::new(&mut gif);
+        for (color, delay) in frames {
+            let buffer = RgbaImage::from_pixel(4, 8, Rgba(*color));
+            let delay = Delay::from_numer_denom_ms(*delay, 1);
+            encoder
+                .encode_frame(Frame::from_parts(buffer, 0, 0, delay))
+                .unwrap();
+        }
+        drop(encoder);
+        gif
+    }
+
+    #[test]
+    fn gif_delays_are_resampled() {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        let gif = gif(&[(RED, 1000), (BLUE, 500), (RED, 200)]);
+
+        let animation =
+            gif_to_animation(gif.as_slice(), 4, 8, 500, &ImportOptions::default()).unwrap();
+        assert_eq!(animation.frames, 3);
+        assert_eq!(animation.speed, 500);
+
+        //4 bytes per plane of a frame, the red planes first then the blue ones
+        let lit = |plane: usize, frame: usize| {
+            let offset = (plane * animation.frames + frame) * 4;
+            animation.data[offset..offset + 4] == [0xff; 4]
+        };
+        assert_eq!(animation.data.len(), 3 * 3 * 4);
+        assert_eq!([lit(0, 0), lit(0, 1), lit(0, 2)], [true, true, false]);
+        assert_eq!([lit(2, 0), lit(2, 1), lit(2, 2)], [false, false, true]);
+        assert!(matches!(
+            animation.payload(),
+            crate::coolled::PayloadType::Animation(_, 3)
+        ));
+    }
+
+    #[test]
+    fn animation_limits() {
+        let long = gif(&[([255; 4], 60_000)]);
+        let options = ImportOptions::default();
+
+        assert!(matches!(
+            gif_to_animation(long.as_slice(), 4, 8, 100, &options),
+            Err(ImportError::Animation(AnimationError::TooManyFrames {
+                frames: 600
+            }))
+        ));
+        assert!(matches!(
+            gif_to_animation(long.as_slice(), 1024, 16, 1000, &options),
+            Err(ImportError::Animation(AnimationError::TooLarge { .. }))
+        ));
+        assert!(matches!(
+            gif_to_animation(long.as_slice(), 4, 8, 0, &options),
+            Err(ImportError::Animation(AnimationError::InvalidSpeed))
+        ));
+        assert!(matches!(
+            gif_to_animation(&b"not a gif"[..], 4, 8, 500, &options),
+            Err(ImportError::Decode(_))
+        ));
+
+        //The frames after the limit are not decoded
+        let mut cut = gif(&[([255; 4], 60_000), ([255; 4], 100)]);
+        cut.truncate(cut.len() - 8);
+        assert!(matches!(
+            gif_to_animation(cut.as_slice(), 4, 8, 100, &options),
+            Err(ImportError::Animation(AnimationError::TooManyFrames {
+                frames: 600
+            }))
+        ));
+    }
+}
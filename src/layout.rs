@@ -0,0 +1,195 @@
+//! Helpers to fit static text in a panel.
+//!
+//! Widths are in columns, as returned by [`Font::measure`].
+
+use crate::font::{Font, GlyphProvider};
+
+/// Horizontal alignment of a text inside the panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    /// Blank columns needed before a text `width` columns wide to align it in
+    /// a panel of `panel_width` columns
+    pub fn offset(&self, width: usize, panel_width: usize) -> usize {
+        let free = panel_width.saturating_sub(width);
+        match self {
+            Align::Left => 0,
+            Align::Center => free / 2,
+            Align::Right => free,
+        }
+    }
+}
+
+/// Split `text` into pages that fit in `panel_width` columns.
+///
+/// Pages are broken on whitespaces when possible, a word wider than the panel
+/// is broken between its characters.
+pub fn pages<'f, 't, 'a, G: GlyphProvider>(
+    font: &'f Font<'a, G>,
+    text: &'t str,
+    panel_width: usize,
+) -> Pages<'f, 't, 'a, G> {
+    Pages {
+        font,
+        text,
+        panel_width,
+    }
+}
+
+/// Iterator returned by [`pages`]
+pub struct Pages<'f, 't, 'a, G> {
+    font: &'f Font<'a, G>,
+    text: &'t str,
+    panel_width: usize,
+}
+
+impl<'f, 't, 'a, G: GlyphProvider> Iterator for Pages<'f, 't, 'a, G> {
+    type Item = &'t str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.text.trim_start();
+        if text.is_empty() {
+            self.text = text;
+            return None;
+        }
+
+        let mut width = 0;
+        let mut last_break = None;
+        let mut end = text.len();
+
+        for (idx, c) in text.char_indices() {
+            let gap = if idx > 0 { self.font.style().gap } else { 0 };
+            let char_width = self.font.char_width(c) + gap;
+
+            if idx > 0 && width + char_width > self.panel_width {
+                end = match last_break {
+                    Some(last_break) => last_break,
+                    None => idx,
+                };
+                break;
+            }
+
+            if c.is_whitespace() {
+                last_break = Some(idx);
+            }
+            width += char_width;
+        }
+
+        self.text = &text[end..];
+        Some(text[..end].trim_end())
+    }
+}
+
+/// Write in `out` the longest start of `text` that fits in `width` columns
+/// followed by an ellipsis, or the whole text if it already fits. Nothing is
+/// written when `width` is too narrow for the ellipsis alone.
+///
+/// The result is cut to the length of `out`.
+pub fn truncate<'b, G: GlyphProvider>(
+    font: &Font<'_, G>,
+    text: &str,
+    width: usize,
+    out: &'b mut [u8],
+) -> &'b str {
+    const ELLIPSIS: char = '…';
+
+    let head = if font.measure(text) <= width {
+        text
+    } else if font.char_width(ELLIPSIS) > width {
+        return "";
+    } else {
+        let gap = font.style().gap;
+        let ellipsis_width = font.char_width(ELLIPSIS) + gap;
+        let mut head_width = 0;
+        let mut end = 0;
+
+        for (idx, c) in text.char_indices() {
+            let char_width = font.char_width(c) + if idx > 0 { gap } else { 0 };
+            if head_width + char_width + ellipsis_width > width {
+                break;
+            }
+            head_width += char_width;
+            end = idx + c.len_utf8();
+        }
+
+        text[..end].trim_end()
+    };
+
+    let mut len = 0;
+    let mut buf = [0u8; 4];
+    let ellipsis = if head.len() < text.len() {
+        Some(ELLIPSIS)
+    } else {
+        None
+    };
+
+    for c in head.chars().chain(ellipsis) {
+        let encoded = c.encode_utf8(&mut buf);
+        if len + encoded.len() > out.len() {
+            break;
+        }
+        out[len..len + encoded.len()].copy_from_slice(encoded.as_bytes());
+        len += encoded.len();
+    }
+
+    //Only whole characters were copied
+    core::str::from_utf8(&out[..len]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::font::UnicodeFont;
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn measure_and_align() {
+        let font = Font::new(UnicodeFont);
+
+        //'T' is 7 columns wide, 'i' 5, with a blank column between them
+        assert_eq!(font.measure("T"), 7);
+        assert_eq!(font.measure("Ti"), 13);
+        assert_eq!(font.measure(""), 0);
+
+        assert_eq!(Align::Left.offset(13, 32), 0);
+        assert_eq!(Align::Center.offset(13, 32), 9);
+        assert_eq!(Align::Right.offset(13, 32), 19);
+        assert_eq!(Align::Right.offset(40, 32), 0);
+    }
+
+    #[test]
+    fn split_pages() {
+        let font = Font::new(UnicodeFont);
+        let text = "Build passed on main";
+
+        let mut count = 0;
+        for page in pages(&font, text, 32) {
+            assert!(font.measure(page) <= 32, "{:?} does not fit", page);
+            assert!(!page.starts_with(' ') && !page.ends_with(' '));
+            count += 1;
+        }
+        assert!(count > 1);
+        assert!(pages(&font, "iiiiiiiiiiiiiiiiiiii", 16).all(|page| font.measure(page) <= 16));
+    }
+
+    #[test]
+    fn truncate_with_ellipsis() {
+        let font = Font::new(UnicodeFont);
+        let mut buf = [0; 64];
+
+        assert_eq!(truncate(&font, "Ti", 32, &mut buf), "Ti");
+
+        let truncated = truncate(&font, "Testing the panel", 32, &mut buf);
+        assert!(truncated.ends_with('…'));
+        assert!(font.measure(truncated) <= 32);
+
+        let narrow = font.char_width('…') - 1;
+        assert_eq!(truncate(&font, "Testing", narrow, &mut buf), "");
+    }
+}
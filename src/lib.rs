@@ -5,9 +5,38 @@
 //! ### Usage
 //! TODO
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+pub mod animation;
+pub mod canvas;
+pub mod charset;
 pub mod colors;
 pub mod coolled;
-mod font;
+#[cfg(feature = "alloc")]
+pub mod effects;
+pub mod font;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+#[cfg(feature = "image")]
+pub mod import;
+pub mod layout;
+pub mod markup;
 mod ncycle;
+pub mod orientation;
 pub mod packets;
-mod util;
\ No newline at end of file
+pub mod patterns;
+#[cfg(feature = "alloc")]
+pub mod procedural;
+#[cfg(feature = "alloc")]
+pub mod preview;
+pub mod small_font;
+#[cfg(feature = "alloc")]
+pub mod transitions;
+mod util;
+pub mod widgets;
+#[cfg(feature = "alloc")]
+pub mod zones;
\ No newline at end of file
@@ -0,0 +1,162 @@
+//! Inline color markup for text payloads.
+//!
+//! A `{color}` tag sets the color of the characters following it, i.e
+//! `"{red}ALERT {white}server down"`. Text before the first tag is white.
+//! `{{` and `}}` are a literal `{` and `}`.
+//!
+//! Tags accept anything parsed by [`CoolLEDColors::from_str`], like `yellow`
+//! or `#ff8000`.
+
+use core::fmt;
+#[cfg(doc)]
+use core::str::FromStr;
+
+use crate::colors::CoolLEDColors;
+
+/// Kind of error found while parsing a markup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkupErrorKind {
+    /// The tag does not name a color
+    UnknownColor,
+    /// A `{` without its closing `}`
+    UnclosedTag,
+    /// A `}` outside of a tag, write `}}` for a literal one
+    UnexpectedBrace,
+    /// The text buffer is too small
+    TextTooLong,
+    /// The colors buffer is too small
+    TooManyCharacters,
+}
+
+/// Error found while parsing a markup, at the byte `position` of the source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkupError {
+    pub kind: MarkupErrorKind,
+    pub position: usize,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            MarkupErrorKind::UnknownColor => "unknown color",
+            MarkupErrorKind::UnclosedTag => "tag is not closed",
+            MarkupErrorKind::UnexpectedBrace => "unexpected '}', use '}}' for a literal one",
+            MarkupErrorKind::TextTooLong => "text buffer is too small",
+            MarkupErrorKind::TooManyCharacters => "colors buffer is too small",
+        };
+        write!(f, "{} at position {}", reason, self.position)
+    }
+}
+
+/// Parse `src`, writing the text in `text` and the color of each of its
+/// characters in `colors`. The results are ready for `PayloadType::Text`.
+pub fn parse<'t, 'c>(
+    src: &str,
+    text: &'t mut [u8],
+    colors: &'c mut [CoolLEDColors],
+) -> Result<(&'t str, &'c [CoolLEDColors]), MarkupError> {
+    let mut current = CoolLEDColors::White;
+    let mut text_len = 0;
+    let mut chars_len = 0;
+
+    let mut it = src.char_indices().peekable();
+    while let Some((position, c)) = it.next() {
+        let error = |kind| MarkupError { kind, position };
+
+        let literal = match c {
+            '{' if it.peek().map(|(_, next)| *next) == Some('{') => {
+                it.next();
+                '{'
+            }
+            '}' if it.peek().map(|(_, next)| *next) == Some('}') => {
+                it.next();
+                '}'
+            }
+            '{' => {
+                let end = src[position..]
+                    .find('}')
+                    .ok_or(error(MarkupErrorKind::UnclosedTag))?;
+                let name = &src[position + 1..position + end];
+
+                current = name
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(MarkupErrorKind::UnknownColor))?;
+                while it.next_if(|(idx, _)| *idx <= position + end).is_some() {}
+                continue;
+            }
+            '}' => return Err(error(MarkupErrorKind::UnexpectedBrace)),
+            c => c,
+        };
+
+        let len = literal.len_utf8();
+        if text_len + len > text.len() {
+            return Err(error(MarkupErrorKind::TextTooLong));
+        }
+        if chars_len >= colors.len() {
+            return Err(error(MarkupErrorKind::TooManyCharacters));
+        }
+
+        literal.encode_utf8(&mut text[text_len..]);
+        colors[chars_len] = current;
+        text_len += len;
+        chars_len += 1;
+    }
+
+    //Only whole characters were written
+    let text = core::str::from_utf8(&text[..text_len]).unwrap_or_default();
+    Ok((text, &colors[..chars_len]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn colored_phrase() {
+        let mut text = [0; 64];
+        let mut colors = [CoolLEDColors::White; 64];
+
+        let (phrase, colors) =
+            parse("ok {Red}ALERT{ white } {{é}}", &mut text, &mut colors).unwrap();
+
+        assert_eq!(phrase, "ok ALERT {é}");
+        assert_eq!(colors.len(), phrase.chars().count());
+        assert_eq!(colors[2], CoolLEDColors::White);
+        assert!(colors[3..8].iter().all(|c| *c == CoolLEDColors::Red));
+        assert!(colors[8..].iter().all(|c| *c == CoolLEDColors::White));
+    }
+
+    #[test]
+    fn errors_have_positions() {
+        let mut text = [0; 64];
+        let mut colors = [CoolLEDColors::White; 64];
+
+        let mut parse_err = |src| parse(src, &mut text, &mut colors).unwrap_err();
+
+        assert_eq!(
+            parse_err("ab {purple}c"),
+            MarkupError {
+                kind: MarkupErrorKind::UnknownColor,
+                position: 3
+            }
+        );
+        assert_eq!(parse_err("abc {red").kind, MarkupErrorKind::UnclosedTag);
+        assert_eq!(
+            parse_err("a}b"),
+            MarkupError {
+                kind: MarkupErrorKind::UnexpectedBrace,
+                position: 1
+            }
+        );
+
+        let mut small = [CoolLEDColors::White; 2];
+        assert_eq!(
+            parse("{red}abc", &mut text, &mut small).unwrap_err(),
+            MarkupError {
+                kind: MarkupErrorKind::TooManyCharacters,
+                position: 7
+            }
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Orientation of the content for panels mounted upside down or seen from
+//! behind a window.
+//!
+//! Set it once on the writer with [`CoolLEDWriter::with_orientation`] to turn
+//! every payload, or apply it to planes with [`Orientation::apply`]. The
+//! panel scrolls in its own directions, send the effect given by
+//! [`Orientation::effect`] for the content to move the expected way.
+
+#[cfg(doc)]
+use crate::coolled::CoolLEDWriter;
+use crate::packets::EffectsMode;
+
+//Bytes of the tallest column handled, 256 rows
+pub(crate) const MAX_COLUMN_SIZE: usize = 32;
+
+/// Transformation of the pixels sent to the panel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Swap the left and right sides
+    pub mirror_horizontal: bool,
+    /// Swap the top and the bottom
+    pub mirror_vertical: bool,
+    /// Light the pixels that are off and turn off the lit ones, black becomes
+    /// white and red becomes cyan
+    pub invert: bool,
+}
+
+impl Orientation {
+    /// Content shown as it is
+    pub const NORMAL: Self = Self {
+        mirror_horizontal: false,
+        mirror_vertical: false,
+        invert: false,
+    };
+
+    /// Content turned by 180°, for panels mounted upside down
+    pub const ROTATE_180: Self = Self {
+        mirror_horizontal: true,
+        mirror_vertical: true,
+        invert: false,
+    };
+
+    /// Whether the content is shown as it is
+    pub fn is_normal(&self) -> bool {
+        *self == Self::NORMAL
+    }
+
+    /// Effect to send for the content to move as `mode` once turned, the
+    /// mirrored sides swap the directions of the scrolling
+    pub fn effect(&self, mode: EffectsMode) -> EffectsMode {
+        match mode {
+            EffectsMode::Left if self.mirror_horizontal => EffectsMode::Right,
+            EffectsMode::Right if self.mirror_horizontal => EffectsMode::Left,
+            EffectsMode::Up if self.mirror_vertical => EffectsMode::Down,
+            EffectsMode::Down if self.mirror_vertical => EffectsMode::Up,
+            mode => mode,
+        }
+    }
+
+    /// Column of the content shown in column `x` of a content `width`
+    /// columns wide
+    pub fn source_column(&self, x: usize, width: usize) -> usize {
+        if self.mirror_horizontal {
+            width - 1 - x
+        } else {
+            x
+        }
+    }
+
+    /// Turn a column of a plane in place, `column` being the `ceil(height /
+    /// 8)` bytes of a column `height` rows tall. Columns up to 256 rows are
+    /// supported.
+    pub fn transform_column(&self, column: &mut [u8], height: usize) {
+        let height = height.min(column.len() * 8).min(MAX_COLUMN_SIZE * 8);
+        let bit = |column: &[u8], y: usize| column[y / 8] & (0x80 >> (y % 8)) != 0;
+
+        if self.mirror_vertical {
+            let mut source = [0; MAX_COLUMN_SIZE];
+            let size = column.len().min(MAX_COLUMN_SIZE);
+            source[..size].copy_from_slice(&column[..size]);
+            column.fill(0);
+            for y in (0..height).filter(|y| bit(&source, height - 1 - y)) {
+                column[y / 8] |= 0x80 >> (y % 8);
+            }
+        }
+        if self.invert {
+            for y in 0..height {
+                column[y / 8] ^= 0x80 >> (y % 8);
+            }
+        }
+    }
+
+    /// Turn in place the planes of an image, or of an animation of `frames`
+    /// frames, `height` rows tall
+    pub fn apply(&self, data: &mut [u8], frames: usize, height: usize) {
+        let column_size = height.div_ceil(8).max(1);
+        let plane_size = data.len() / 3 / frames.max(1);
+        if plane_size == 0 {
+            return;
+        }
+
+        for plane in data.chunks_exact_mut(plane_size) {
+            if self.mirror_horizontal {
+                let width = plane.len() / column_size;
+                for x in 0..width / 2 {
+                    let (left, right) = plane.split_at_mut((width - 1 - x) * column_size);
+                    left[x * column_size..(x + 1) * column_size]
+                        .swap_with_slice(&mut right[..column_size]);
+                }
+            }
+            for column in plane.chunks_exact_mut(column_size) {
+                self.transform_column(column, height);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::colors::CoolLEDColors;
+
+    fn planes(canvas: &Canvas<3, 12>) -> [u8; Canvas::<3, 12>::SIZE] {
+        let mut data = [0; Canvas::<3, 12>::SIZE];
+        canvas.write_planes(&mut data);
+        data
+    }
+
+    #[test]
+    fn mirrors_and_rotation() {
+        let mut canvas = Canvas::<3, 12>::new();
+        canvas.set_pixel(0, 0, CoolLEDColors::Red);
+        canvas.set_pixel(1, 10, CoolLEDColors::Cyan);
+
+        let mut turned = Canvas::<3, 12>::new();
+        turned.set_pixel(2, 11, CoolLEDColors::Red);
+        turned.set_pixel(1, 1, CoolLEDColors::Cyan);
+        let mut data = planes(&canvas);
+        Orientation::ROTATE_180.apply(&mut data, 1, 12);
+        assert_eq!(data, planes(&turned));
+
+        let mut mirrored = Canvas::<3, 12>::new();
+        mirrored.set_pixel(2, 0, CoolLEDColors::Red);
+        mirrored.set_pixel(1, 10, CoolLEDColors::Cyan);
+        let mut data = planes(&canvas);
+        let horizontal = Orientation {
+            mirror_horizontal: true,
+            ..Default::default()
+        };
+        horizontal.apply(&mut data, 1, 12);
+        assert_eq!(data, planes(&mirrored));
+        assert_eq!(horizontal.source_column(0, 3), 2);
+    }
+
+    #[test]
+    fn mirrors_swap_the_scrolling() {
+        assert_eq!(
+            Orientation::ROTATE_180.effect(EffectsMode::Left),
+            EffectsMode::Right
+        );
+        assert_eq!(
+            Orientation::ROTATE_180.effect(EffectsMode::Up),
+            EffectsMode::Down
+        );
+        let vertical = Orientation {
+            mirror_vertical: true,
+            ..Default::default()
+        };
+        assert_eq!(vertical.effect(EffectsMode::Left), EffectsMode::Left);
+        assert_eq!(vertical.effect(EffectsMode::Down), EffectsMode::Up);
+        assert_eq!(
+            Orientation::NORMAL.effect(EffectsMode::Snowflake),
+            EffectsMode::Snowflake
+        );
+    }
+
+    #[test]
+    fn invert_keeps_the_padding() {
+        let mut canvas = Canvas::<3, 12>::new();
+        canvas.fill(CoolLEDColors::Yellow);
+        canvas.set_pixel(1, 1, CoolLEDColors::White);
+
+        let mut inverted = Canvas::<3, 12>::new();
+        inverted.fill(CoolLEDColors::Blue);
+        inverted.set_pixel(1, 1, CoolLEDColors::Black);
+        let mut data = planes(&canvas);
+        Orientation {
+            invert: true,
+            ..Default::default()
+        }
+        .apply(&mut data, 1, 12);
+        assert_eq!(data, planes(&inverted));
+    }
+
+    #[test]
+    fn animation_frames_are_turned() {
+        //Two frames of one column, 8 rows
+        let mut data = [0x80, 0x01, 0, 0, 0, 0];
+        let vertical = Orientation {
+            mirror_vertical: true,
+            ..Default::default()
+        };
+        vertical.apply(&mut data, 2, 8);
+        assert_eq!(data, [0x01, 0x80, 0, 0, 0, 0]);
+    }
+}
@@ -30,6 +30,7 @@ pub enum PacketType {
 }
 
 /// Features of effects to be sent to the display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EffectsMode {
     Static = 0x1,
     Left = 0x2,
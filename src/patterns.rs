@@ -0,0 +1,225 @@
+//! Color schemes for text payloads.
+//!
+//! Each function writes the color of every character of `text` in `out` and
+//! returns the written part, ready for `PayloadType::Text`. `out` must hold at
+//! least one color per character, [`BufferTooSmall`] is returned otherwise.
+
+use core::fmt;
+
+use crate::colors::CoolLEDColors;
+
+/// Error returned when the buffer holds fewer colors than the text has
+/// characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    /// Number of characters of the text
+    pub needed: usize,
+    /// Length of the buffer
+    pub len: usize,
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} characters to color in a buffer of {} colors",
+            self.needed, self.len
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferTooSmall {}
+
+/// Colors of the rainbow, in order
+pub const RAINBOW: [CoolLEDColors; 6] = [
+    CoolLEDColors::Red,
+    CoolLEDColors::Yellow,
+    CoolLEDColors::Green,
+    CoolLEDColors::Cyan,
+    CoolLEDColors::Blue,
+    CoolLEDColors::Pink,
+];
+
+/// Colors of the characters, by class
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClassColors {
+    pub digit: CoolLEDColors,
+    pub letter: CoolLEDColors,
+    pub other: CoolLEDColors,
+}
+
+/// Rainbow across the characters, whitespaces do not use a color
+pub fn rainbow<'c>(
+    text: &str,
+    out: &'c mut [CoolLEDColors],
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    cycle(text, &RAINBOW, out)
+}
+
+/// Cycle `palette` across the characters, whitespaces do not use a color.
+///
+/// Panics if `palette` is empty.
+pub fn cycle<'c>(
+    text: &str,
+    palette: &[CoolLEDColors],
+    out: &'c mut [CoolLEDColors],
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    let mut idx = 0;
+    fill(text, out, |c| {
+        let color = palette[idx % palette.len()];
+        if !c.is_whitespace() {
+            idx += 1;
+        }
+        color
+    })
+}
+
+/// Cycle `palette` across the words, the whitespaces take the color of the
+/// word before them.
+///
+/// Panics if `palette` is empty.
+pub fn alternate_words<'c>(
+    text: &str,
+    palette: &[CoolLEDColors],
+    out: &'c mut [CoolLEDColors],
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    let mut word = 0;
+    let mut previous_whitespace = false;
+    fill(text, out, |c| {
+        if previous_whitespace && !c.is_whitespace() {
+            word += 1;
+        }
+        previous_whitespace = c.is_whitespace();
+        palette[word % palette.len()]
+    })
+}
+
+/// Color the digits, the letters and the other characters apart
+pub fn by_class<'c>(
+    text: &str,
+    colors: ClassColors,
+    out: &'c mut [CoolLEDColors],
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    fill(text, out, |c| {
+        if c.is_numeric() {
+            colors.digit
+        } else if c.is_alphabetic() {
+            colors.letter
+        } else {
+            colors.other
+        }
+    })
+}
+
+/// Color with `highlight` every occurrence of the `needles`, the rest with
+/// `base`
+pub fn highlight<'c>(
+    text: &str,
+    needles: &[&str],
+    base: CoolLEDColors,
+    highlight: CoolLEDColors,
+    out: &'c mut [CoolLEDColors],
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    let mut highlighted_until = 0;
+    let mut chars = text.char_indices();
+    fill(text, out, |_| {
+        let (idx, _) = chars.next().unwrap_or_default();
+
+        for needle in needles.iter().filter(|needle| !needle.is_empty()) {
+            if text[idx..].starts_with(needle) {
+                highlighted_until = highlighted_until.max(idx + needle.len());
+            }
+        }
+
+        if idx < highlighted_until {
+            highlight
+        } else {
+            base
+        }
+    })
+}
+
+fn fill<'c, F: FnMut(char) -> CoolLEDColors>(
+    text: &str,
+    out: &'c mut [CoolLEDColors],
+    mut color: F,
+) -> Result<&'c [CoolLEDColors], BufferTooSmall> {
+    let needed = text.chars().count();
+    if out.len() < needed {
+        return Err(BufferTooSmall {
+            needed,
+            len: out.len(),
+        });
+    }
+
+    for (slot, c) in out.iter_mut().zip(text.chars()) {
+        *slot = color(c);
+    }
+    Ok(&out[..needed])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use CoolLEDColors::*;
+
+    #[test]
+    fn rainbow_skips_whitespaces() {
+        let mut out = [White; 16];
+        assert_eq!(
+            rainbow("ab cdefgh", &mut out).unwrap(),
+            &[Red, Yellow, Green, Green, Cyan, Blue, Pink, Red, Yellow]
+        );
+    }
+
+    #[test]
+    fn words_and_classes() {
+        let mut out = [White; 16];
+        assert_eq!(
+            alternate_words("ab  c d", &[Red, Blue], &mut out).unwrap(),
+            &[Red, Red, Red, Red, Blue, Blue, Red]
+        );
+
+        let classes = ClassColors {
+            digit: Green,
+            letter: White,
+            other: Red,
+        };
+        assert_eq!(
+            by_class("CPU 9%", classes, &mut out).unwrap(),
+            &[White, White, White, Red, Green, Red]
+        );
+    }
+
+    #[test]
+    fn highlight_matches() {
+        let mut out = [White; 16];
+        assert_eq!(
+            highlight("failed: api", &["fail", "api"], White, Red, &mut out).unwrap(),
+            &[Red, Red, Red, Red, White, White, White, White, Red, Red, Red]
+        );
+
+        assert_eq!(
+            highlight("a bb a", &["a"], Blue, Yellow, &mut out).unwrap(),
+            &[Yellow, Blue, Blue, Blue, Blue, Yellow]
+        );
+    }
+
+    #[test]
+    fn short_buffer_is_an_error() {
+        let text = "abcdefgh";
+        let mut out = [White; 3];
+        assert_eq!(
+            rainbow(text, &mut out),
+            Err(BufferTooSmall { needed: 8, len: 3 })
+        );
+
+        let mut out = [];
+        assert_eq!(
+            cycle(text, &[Red], &mut out),
+            Err(BufferTooSmall { needed: 8, len: 0 })
+        );
+        assert!(cycle("", &[Red], &mut out).unwrap().is_empty());
+    }
+}
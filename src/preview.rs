@@ -0,0 +1,428 @@
+//! Preview of payloads without a device.
+//!
+//! [`Preview`] decodes the data a writer sends back into frames of colors.
+//! Texts are shown as the strip of columns the device scrolls, or played with
+//! one of the [`EffectsMode`]s by [`Preview::with_effect`].
+//!
+//! Frames are printed in the terminal with ANSI colors, and with the `std`
+//! feature animations are played in place. With the `image` feature they are
+//! rendered to PNG or animated GIF files with an LED dot look.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::colors::CoolLEDColors;
+use crate::coolled::{CoolLEDWriter, PayloadType};
+use crate::font::GlyphProvider;
+use crate::packets::EffectsMode;
+
+//Rows of the text columns, the height of the glyphs
+const TEXT_HEIGHT: usize = 16;
+
+/// Frames of a payload as shown on the panel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preview {
+    pub width: usize,
+    pub height: usize,
+    /// Colors of the pixels of every frame, row by row
+    pub frames: Vec<Vec<CoolLEDColors>>,
+    /// Delay between the frames in milliseconds
+    pub speed: u16,
+}
+
+impl Preview {
+    /// Decode the payload of `writer` for a panel `height` pixels tall. Texts
+    /// are always as tall as the glyphs.
+    pub fn new<G: GlyphProvider>(writer: &CoolLEDWriter<'_, G>, height: usize) -> Self {
+        let (height, frames) = match writer.payload() {
+            PayloadType::Text(_, _) => (TEXT_HEIGHT, 1),
+            PayloadType::Image(_) => (height, 1),
+            PayloadType::Animation(_, frames) => (height, (*frames).max(1)),
+        };
+
+        let mut data = vec![0; writer.data_size()];
+        writer.write_data(&mut data);
+
+        let column_size = height.div_ceil(8).max(1);
+        let plane = data.len() / 3 / frames;
+        let width = plane / column_size;
+
+        let lit = |plane_idx: usize, frame: usize, x: usize, y: usize| {
+            let byte = data[(plane_idx * frames + frame) * plane + x * column_size + y / 8];
+            byte & (0x80 >> (y % 8)) != 0
+        };
+        let frames = (0..frames)
+            .map(|frame| {
+                (0..width * height)
+                    .map(|idx| {
+                        let (x, y) = (idx % width, idx / width);
+                        CoolLEDColors::from_planes(
+                            lit(0, frame, x, y),
+                            lit(1, frame, x, y),
+                            lit(2, frame, x, y),
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            width,
+            height,
+            frames,
+            speed: writer.animation_speed(),
+        }
+    }
+
+    /// Color of a pixel of a frame
+    pub fn pixel(&self, frame: usize, x: usize, y: usize) -> CoolLEDColors {
+        self.frames[frame][y * self.width + x]
+    }
+
+    /// Preview of the first frame of the payload played with `mode` on a
+    /// panel `panel_width` columns wide, a frame every `speed` milliseconds.
+    /// The effects moving the content scroll it by a pixel per frame, until
+    /// it has left the panel. Other effects are shown as static.
+    pub fn with_effect(&self, mode: EffectsMode, panel_width: usize, speed: u16) -> Self {
+        let (width, height) = (panel_width, self.height);
+        //Panel showing the content moved by (-dx, -dy)
+        let view = |(dx, dy): (isize, isize)| -> Vec<CoolLEDColors> {
+            (0..width * height)
+                .map(|idx| {
+                    let x = (idx % width) as isize + dx;
+                    let y = (idx / width) as isize + dy;
+                    if (0..self.width as isize).contains(&x) && (0..height as isize).contains(&y) {
+                        self.pixel(0, x as usize, y as usize)
+                    } else {
+                        CoolLEDColors::Black
+                    }
+                })
+                .collect()
+        };
+
+        let (content, panel, rows) = (self.width as isize, width as isize, height as isize);
+        let frames = match mode {
+            EffectsMode::Left => (-panel..content).map(|dx| view((dx, 0))).collect(),
+            EffectsMode::Right => (1 - panel..=content)
+                .rev()
+                .map(|dx| view((dx, 0)))
+                .collect(),
+            EffectsMode::Up => (-rows..rows).map(|dy| view((0, dy))).collect(),
+            EffectsMode::Down => (-rows..rows).rev().map(|dy| view((0, dy))).collect(),
+            _ => vec![view((0, 0))],
+        };
+
+        Self {
+            width,
+            height,
+            frames,
+            speed,
+        }
+    }
+
+    /// A frame as lines of ANSI colored half blocks, two rows per line
+    pub fn ansi(&self, frame: usize) -> String {
+        //Unlit LEDs are dark gray
+        let code = |color: CoolLEDColors| match color {
+            CoolLEDColors::Black => 90,
+            CoolLEDColors::Red => 91,
+            CoolLEDColors::Green => 92,
+            CoolLEDColors::Yellow => 93,
+            CoolLEDColors::Blue => 94,
+            CoolLEDColors::Pink => 95,
+            CoolLEDColors::Cyan => 96,
+            CoolLEDColors::White => 97,
+        };
+
+        let mut out = String::new();
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let top = self.pixel(frame, x, y);
+                let bottom = if y + 1 < self.height {
+                    code(self.pixel(frame, x, y + 1)) + 10
+                } else {
+                    49
+                };
+                let _ = write!(out, "\x1b[{};{}m\u{2580}", code(top), bottom);
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+
+    /// Print the frames `loops` times in the terminal, each over the previous
+    /// one
+    #[cfg(feature = "std")]
+    pub fn play<W: std::io::Write>(&self, mut out: W, loops: usize) -> std::io::Result<()> {
+        let lines = self.height.div_ceil(2);
+        for idx in 0..loops * self.frames.len() {
+            if idx > 0 {
+                write!(out, "\x1b[{}A", lines)?;
+            }
+            out.write_all(self.ansi(idx % self.frames.len()).as_bytes())?;
+            out.flush()?;
+            std::thread::sleep(std::time::Duration::from_millis(self.speed as u64));
+        }
+        Ok(())
+    }
+}
+
+/// Look of the rendered LEDs
+#[cfg(feature = "image")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreviewStyle {
+    /// Size in pixels of a LED and the space around it
+    pub scale: u32,
+    /// Draw round LEDs separated by the background instead of squares
+    pub dots: bool,
+    /// Color of the unlit LEDs
+    pub off: [u8; 3],
+    /// Color between the LEDs
+    pub background: [u8; 3],
+}
+
+#[cfg(feature = "image")]
+impl Default for PreviewStyle {
+    fn default() -> Self {
+        Self {
+            scale: 8,
+            dots: true,
+            off: [32, 32, 32],
+            background: [0, 0, 0],
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+impl Preview {
+    /// Render a frame
+    pub fn render(&self, frame: usize, style: &PreviewStyle) -> image::RgbImage {
+        let scale = style.scale.max(1);
+        //Squared radius of the LEDs, in half pixels from their center
+        let radius = (scale * 4 / 5).pow(2);
+
+        image::RgbImage::from_fn(
+            self.width as u32 * scale,
+            self.height as u32 * scale,
+            |x, y| {
+                let (dx, dy) = ((x % scale) * 2 + 1, (y % scale) * 2 + 1);
+                let inside = dx.abs_diff(scale).pow(2) + dy.abs_diff(scale).pow(2) <= radius;
+                if style.dots && !inside {
+                    return image::Rgb(style.background);
+                }
+
+                let color = self.pixel(frame, (x / scale) as usize, (y / scale) as usize);
+                if color == CoolLEDColors::Black {
+                    image::Rgb(style.off)
+                } else {
+                    image::Rgb(color.to_rgb())
+                }
+            },
+        )
+    }
+
+    /// Write a frame as a PNG file
+    pub fn write_png<W: std::io::Write>(
+        &self,
+        frame: usize,
+        style: &PreviewStyle,
+        out: W,
+    ) -> image::ImageResult<()> {
+        use image::ImageEncoder;
+
+        let image = self.render(frame, style);
+        image::codecs::png::PngEncoder::new(out).write_image(
+            &image,
+            image.width(),
+            image.height(),
+            image::ColorType::Rgb8,
+        )
+    }
+
+    /// Write every frame as an animated GIF file, looping forever
+    pub fn write_gif<W: std::io::Write>(
+        &self,
+        style: &PreviewStyle,
+        out: W,
+    ) -> image::ImageResult<()> {
+        use image::codecs::gif::{GifEncoder, Repeat};
+
+        let mut encoder = GifEncoder::new(out);
+        encoder.set_repeat(Repeat::Infinite)?;
+        for frame in 0..self.frames.len() {
+            let buffer = image::DynamicImage::ImageRgb8(self.render(frame, style)).into_rgba8();
+            let delay = image::Delay::from_numer_denom_ms(self.speed as u32, 1);
+            encoder.encode_frame(image::Frame::from_parts(buffer, 0, 0, delay))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::animation::AnimationBuilder;
+    use crate::canvas::Canvas;
+
+    #[test]
+    fn image_and_animation_frames() {
+        let mut canvas = Canvas::<4, 12>::new();
+        canvas.set_pixel(1, 10, CoolLEDColors::Yellow);
+        let mut data = [0; Canvas::<4, 12>::SIZE];
+        canvas.write_planes(&mut data);
+
+        let preview = Preview::new(&CoolLEDWriter::new(PayloadType::Image(&data)), 12);
+        assert_eq!((preview.width, preview.height), (4, 12));
+        assert_eq!(preview.pixel(0, 1, 10), CoolLEDColors::Yellow);
+        assert_eq!(preview.pixel(0, 1, 9), CoolLEDColors::Black);
+
+        let mut builder = AnimationBuilder::new().with_speed(250);
+        builder.push_frame(&Canvas::<4, 12>::new()).unwrap();
+        builder.push_frame(&canvas).unwrap();
+        let animation = builder.build().unwrap();
+
+        let preview = Preview::new(&animation.writer(), 12);
+        assert_eq!(preview.frames.len(), 2);
+        assert_eq!(preview.speed, 250);
+        assert_eq!(preview.pixel(0, 1, 10), CoolLEDColors::Black);
+        assert_eq!(preview.pixel(1, 1, 10), CoolLEDColors::Yellow);
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn text_strip() {
+        let colors = [CoolLEDColors::Red, CoolLEDColors::Cyan];
+        let writer = CoolLEDWriter::new(PayloadType::Text("Ti", &colors));
+        let preview = Preview::new(&writer, 8);
+
+        let font = crate::font::Font::new(crate::font::UnicodeFont);
+        assert_eq!(preview.height, 16);
+        assert_eq!(preview.width, font.measure("Ti") + 1);
+
+        //The bar of the 'T' on the third row
+        assert_eq!(preview.pixel(0, 0, 2), CoolLEDColors::Red);
+        assert_eq!(preview.pixel(0, 0, 1), CoolLEDColors::Black);
+        let cyan = (8..preview.width)
+            .any(|x| (0..16).any(|y| preview.pixel(0, x, y) == CoolLEDColors::Cyan));
+        assert!(cyan);
+    }
+
+    #[test]
+    fn scrolling_effects() {
+        let mut canvas = Canvas::<2, 2>::new();
+        canvas.fill(CoolLEDColors::Red);
+        let mut data = [0; Canvas::<2, 2>::SIZE];
+        canvas.write_planes(&mut data);
+        let preview = Preview::new(&CoolLEDWriter::new(PayloadType::Image(&data)), 2);
+
+        let left = preview.with_effect(EffectsMode::Left, 3, 50);
+        assert_eq!(left.frames.len(), 5);
+        assert_eq!((left.width, left.height, left.speed), (3, 2, 50));
+        //The text enters on the right and leaves on the left
+        let row = |preview: &Preview, frame| {
+            (0..3)
+                .map(|x| preview.pixel(frame, x, 0) == CoolLEDColors::Red)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row(&left, 0), [false, false, false]);
+        assert_eq!(row(&left, 1), [false, false, true]);
+        assert_eq!(row(&left, 4), [true, false, false]);
+
+        let right = preview.with_effect(EffectsMode::Right, 3, 50);
+        assert_eq!(row(&right, 1), [true, false, false]);
+
+        let up = preview.with_effect(EffectsMode::Up, 3, 50);
+        assert_eq!(up.frames.len(), 4);
+        assert_eq!(up.pixel(1, 0, 1), CoolLEDColors::Red);
+        assert_eq!(up.pixel(1, 0, 0), CoolLEDColors::Black);
+
+        let still = preview.with_effect(EffectsMode::Snowflake, 3, 50);
+        assert_eq!(row(&still, 0), [true, true, false]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "full-font", feature = "latin"))]
+    fn rotated_text_reads_left_to_right() {
+        use crate::orientation::Orientation;
+
+        let colors = [CoolLEDColors::Red, CoolLEDColors::Cyan];
+        let writer = CoolLEDWriter::new(PayloadType::Text("Ti", &colors)).with_orientation(
+            Orientation::ROTATE_180,
+            32,
+            16,
+        );
+        let mode = Orientation::ROTATE_180.effect(EffectsMode::Left);
+        let scrolled = Preview::new(&writer, 16).with_effect(mode, 32, 50);
+
+        //Columns of a color in a frame, as seen once the panel is turned
+        let columns = |frame: usize, color: CoolLEDColors| {
+            (0..32)
+                .filter(|x| (0..16).any(|y| scrolled.pixel(frame, 31 - x, 15 - y) == color))
+                .collect::<Vec<_>>()
+        };
+        let lit = |frame| {
+            !columns(frame, CoolLEDColors::Red).is_empty()
+                || !columns(frame, CoolLEDColors::Cyan).is_empty()
+        };
+
+        //The 'T' enters first on the right side, and stays left of the 'i'
+        let first = (0..scrolled.frames.len())
+            .find(|frame| lit(*frame))
+            .unwrap();
+        assert_eq!(columns(first, CoolLEDColors::Red), [31]);
+        assert!(columns(first, CoolLEDColors::Cyan).is_empty());
+        let both = (first..scrolled.frames.len())
+            .find(|frame| !columns(*frame, CoolLEDColors::Cyan).is_empty())
+            .unwrap();
+        assert!(columns(both, CoolLEDColors::Red)[0] < columns(both, CoolLEDColors::Cyan)[0]);
+    }
+
+    #[test]
+    fn ansi_half_blocks() {
+        let mut canvas = Canvas::<2, 3>::new();
+        canvas.set_pixel(0, 0, CoolLEDColors::Red);
+        canvas.set_pixel(1, 1, CoolLEDColors::Blue);
+        canvas.set_pixel(0, 2, CoolLEDColors::White);
+        let mut data = [0; Canvas::<2, 3>::SIZE];
+        canvas.write_planes(&mut data);
+        let preview = Preview::new(&CoolLEDWriter::new(PayloadType::Image(&data)), 3);
+
+        assert_eq!(
+            preview.ansi(0),
+            "\x1b[91;100m\u{2580}\x1b[90;104m\u{2580}\x1b[0m\n\
+             \x1b[97;49m\u{2580}\x1b[90;49m\u{2580}\x1b[0m\n"
+        );
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_led_dots() {
+        use image::AnimationDecoder;
+
+        let mut canvas = Canvas::<2, 8>::new();
+        canvas.set_pixel(0, 0, CoolLEDColors::Green);
+        let mut builder = AnimationBuilder::new();
+        builder.push_frame(&canvas).unwrap();
+        builder.push_frame(&Canvas::new()).unwrap();
+        let animation = builder.build().unwrap();
+        let preview = Preview::new(&animation.writer(), 8);
+
+        let style = PreviewStyle::default();
+        let image = preview.render(0, &style);
+        assert_eq!(image.dimensions(), (16, 64));
+        assert_eq!(image.get_pixel(4, 4).0, [0, 255, 0]);
+        assert_eq!(image.get_pixel(0, 0).0, style.background);
+        assert_eq!(image.get_pixel(12, 4).0, style.off);
+
+        let mut png = Vec::new();
+        preview.write_png(0, &style, &mut png).unwrap();
+        assert_eq!(image::load_from_memory(&png).unwrap().to_rgb8(), image);
+
+        let mut gif = Vec::new();
+        preview.write_gif(&style, &mut gif).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(gif.as_slice()).unwrap();
+        assert_eq!(decoder.into_frames().count(), 2);
+    }
+}
@@ -0,0 +1,361 @@
+//! Procedural animations, for idle screens or to try large payloads.
+//!
+//! ```
+//! use coolled1248::animation::AnimationBuilder;
+//! use coolled1248::colors::CoolLEDColors;
+//! use coolled1248::procedural::Procedural;
+//!
+//! let mut builder = AnimationBuilder::<32, 16>::new().with_speed(80);
+//! Procedural::MatrixRain { seed: 42, drops: 12 }.push_frames(&mut builder, 100)?;
+//! Procedural::Spinner { tail: 3, color: CoolLEDColors::Cyan }.push_frames(&mut builder, 16)?;
+//!
+//! let animation = builder.build()?;
+//! assert_eq!(animation.frames, 116);
+//! # Ok::<(), coolled1248::animation::AnimationError>(())
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::animation::{check_limits, AnimationBuilder, AnimationError};
+use crate::canvas::Canvas;
+use crate::colors::{CoolLEDColors, PALETTE};
+use crate::util::XorShift;
+
+/// Procedural animation, the random ones are the same for the same `seed`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Procedural {
+    /// `drops` green drops with a white head falling down the columns
+    MatrixRain { seed: u32, drops: usize },
+    /// `stars` stars moving to the left, the faster ones brighter
+    Starfield { seed: u32, stars: usize },
+    /// Waves of colors, `period` pixels wide
+    Plasma { period: usize },
+    /// `sparks` random pixels lit in random colors every frame
+    Sparkle { seed: u32, sparks: usize },
+    /// A ball `size` pixels wide bouncing on the sides of the panel
+    BouncingBall { size: usize, color: CoolLEDColors },
+    /// Conway's Game of Life on a panel wrapping around, `density` percent of
+    /// the cells alive at first
+    Life { seed: u32, density: usize },
+    /// A dot followed by `tail` dots turning around the center of the panel,
+    /// a turn every 8 frames
+    Spinner { tail: usize, color: CoolLEDColors },
+}
+
+//Colors of the palette that are lit
+const LIT: &[CoolLEDColors] = PALETTE.split_at(7).0;
+
+//Points of the spinner around a circle of radius 100
+const CIRCLE: [(i32, i32); 8] = [
+    (0, -100),
+    (71, -71),
+    (100, 0),
+    (71, 71),
+    (0, 100),
+    (-71, 71),
+    (-100, 0),
+    (-71, -71),
+];
+
+impl Procedural {
+    /// Push `frames` frames of the animation to `builder`. Fails without
+    /// pushing a frame when the animation would exceed the limits of the
+    /// device.
+    pub fn push_frames<const W: usize, const H: usize>(
+        &self,
+        builder: &mut AnimationBuilder<W, H>,
+        frames: usize,
+    ) -> Result<(), AnimationError> {
+        check_limits(builder.frames().saturating_add(frames), Canvas::<W, H>::PLANE_SIZE)?;
+        for frame in &self.frames::<W, H>(frames) {
+            builder.push_frame(frame)?;
+        }
+        Ok(())
+    }
+
+    fn frames<const W: usize, const H: usize>(&self, count: usize) -> Vec<Canvas<W, H>> {
+        let (width, height) = (W as i32, H as i32);
+        let mut frames = vec![Canvas::<W, H>::new(); count];
+        match *self {
+            Procedural::MatrixRain { seed, drops } => {
+                let mut random = XorShift::new(seed);
+                //Column, row of the head and length of the drops
+                let drop = |random: &mut XorShift, row: i32| {
+                    let length = 2 + random.below(H / 2 + 1) as i32;
+                    (random.below(W) as i32, row, length)
+                };
+                let mut drops: Vec<_> = (0..drops)
+                    .map(|_| {
+                        let row = random.below(2 * H) as i32 - height;
+                        drop(&mut random, row)
+                    })
+                    .collect();
+                for frame in &mut frames {
+                    for (x, y, length) in &mut drops {
+                        frame.fill_rect(
+                            *x,
+                            *y - *length,
+                            1,
+                            *length as usize,
+                            CoolLEDColors::Green,
+                        );
+                        frame.set_pixel(*x, *y, CoolLEDColors::White);
+                        *y += 1;
+                        if *y - *length >= height {
+                            let row = -(random.below(H) as i32) - 1;
+                            (*x, *y, *length) = drop(&mut random, row);
+                        }
+                    }
+                }
+            }
+            Procedural::Starfield { seed, stars } => {
+                let mut random = XorShift::new(seed);
+                //Column, row and speed of the stars
+                let mut stars: Vec<_> = (0..stars)
+                    .map(|_| {
+                        let x = random.below(W) as i32;
+                        (x, random.below(H) as i32, 1 + random.below(3))
+                    })
+                    .collect();
+                let colors = [
+                    CoolLEDColors::Blue,
+                    CoolLEDColors::Cyan,
+                    CoolLEDColors::White,
+                ];
+                for frame in &mut frames {
+                    for (x, y, speed) in &mut stars {
+                        frame.set_pixel(*x, *y, colors[*speed - 1]);
+                        *x -= *speed as i32;
+                        if *x < 0 {
+                            *x += width;
+                            *y = random.below(H) as i32;
+                        }
+                    }
+                }
+            }
+            Procedural::Plasma { period } => {
+                let period = period.max(1);
+                //Triangle wave between 0 and 254
+                let wave = |phase: usize| {
+                    let phase = phase % 256;
+                    2 * phase.min(255 - phase)
+                };
+                for (t, frame) in frames.iter_mut().enumerate() {
+                    for (x, y) in (0..H).flat_map(|y| (0..W).map(move |x| (x, y))) {
+                        let value = wave(x * 256 / period + t * 8)
+                            + wave(y * 256 / period + t * 4)
+                            + wave((x + y) * 128 / period + t * 12);
+                        let color = LIT[value * LIT.len() / 763];
+                        frame.set_pixel(x as i32, y as i32, color);
+                    }
+                }
+            }
+            Procedural::Sparkle { seed, sparks } => {
+                let mut random = XorShift::new(seed);
+                for frame in &mut frames {
+                    for _ in 0..sparks {
+                        let (x, y) = (random.below(W) as i32, random.below(H) as i32);
+                        frame.set_pixel(x, y, LIT[random.below(LIT.len())]);
+                    }
+                }
+            }
+            Procedural::BouncingBall { size, color } => {
+                let size = size as i32;
+                let (mut x, mut y, mut dx, mut dy) = (0, 0, 1, 1);
+                let bounce = |position: &mut i32, speed: &mut i32, max: i32| {
+                    if max > 0 {
+                        if !(0..=max).contains(&(*position + *speed)) {
+                            *speed = -*speed;
+                        }
+                        *position += *speed;
+                    }
+                };
+                for frame in &mut frames {
+                    //Pixels of the square whose center is in the ball
+                    for (px, py) in (0..size).flat_map(|py| (0..size).map(move |px| (px, py))) {
+                        let (cx, cy) = (2 * px + 1 - size, 2 * py + 1 - size);
+                        if cx * cx + cy * cy <= size * size {
+                            frame.set_pixel(x + px, y + py, color);
+                        }
+                    }
+                    bounce(&mut x, &mut dx, width - size);
+                    bounce(&mut y, &mut dy, height - size);
+                }
+            }
+            Procedural::Life { seed, density } => {
+                let mut random = XorShift::new(seed);
+                let mut cells: Vec<bool> =
+                    (0..W * H).map(|_| random.below(100) < density).collect();
+                for frame in &mut frames {
+                    for (idx, _) in cells.iter().enumerate().filter(|(_, alive)| **alive) {
+                        frame.set_pixel((idx % W) as i32, (idx / W) as i32, CoolLEDColors::Green);
+                    }
+                    cells = life_step(&cells, W, H);
+                }
+            }
+            Procedural::Spinner { tail, color } => {
+                let radius = (width.min(height) - 1) / 2;
+                let (cx, cy) = ((width - 1) / 2, (height - 1) / 2);
+                for (t, frame) in frames.iter_mut().enumerate() {
+                    for dot in 0..=tail.min(CIRCLE.len() - 1) {
+                        let (x, y) = CIRCLE[(t + CIRCLE.len() - dot) % CIRCLE.len()];
+                        frame.set_pixel(cx + x * radius / 100, cy + y * radius / 100, color);
+                    }
+                }
+            }
+        }
+        frames
+    }
+}
+
+//Next generation of the cells of a `width` by `height` panel wrapping around
+fn life_step(cells: &[bool], width: usize, height: usize) -> Vec<bool> {
+    (0..width * height)
+        .map(|idx| {
+            let (x, y) = (idx % width, idx / width);
+            let neighbours = [
+                (width - 1, height - 1),
+                (0, height - 1),
+                (1, height - 1),
+                (width - 1, 0),
+            ]
+            .into_iter()
+            .chain([(1, 0), (width - 1, 1), (0, 1), (1, 1)])
+            .filter(|(dx, dy)| cells[(y + dy) % height * width + (x + dx) % width])
+            .count();
+            neighbours == 3 || (cells[idx] && neighbours == 2)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lit(canvas: &Canvas<16, 8>, color: CoolLEDColors) -> usize {
+        (0..16)
+            .flat_map(|x| (0..8).map(move |y| (x, y)))
+            .filter(|(x, y)| canvas.pixel(*x, *y) == Some(color))
+            .count()
+    }
+
+    #[test]
+    fn random_animations_follow_the_seed() {
+        for animation in [
+            Procedural::MatrixRain { seed: 1, drops: 6 },
+            Procedural::Starfield { seed: 1, stars: 10 },
+            Procedural::Sparkle { seed: 1, sparks: 5 },
+            Procedural::Life {
+                seed: 1,
+                density: 30,
+            },
+        ] {
+            let frames = animation.frames::<16, 8>(20);
+            assert_eq!(frames.len(), 20);
+            assert_eq!(frames, animation.frames::<16, 8>(20));
+            assert!(frames
+                .iter()
+                .all(|frame| lit(frame, CoolLEDColors::Black) < 128));
+        }
+        assert_ne!(
+            Procedural::Sparkle { seed: 1, sparks: 5 }.frames::<16, 8>(1),
+            Procedural::Sparkle { seed: 2, sparks: 5 }.frames::<16, 8>(1)
+        );
+    }
+
+    #[test]
+    fn rain_and_stars() {
+        let rain = Procedural::MatrixRain { seed: 5, drops: 4 }.frames::<16, 8>(30);
+        assert!(rain
+            .iter()
+            .any(|frame| lit(frame, CoolLEDColors::White) > 0));
+        assert!(rain
+            .iter()
+            .any(|frame| lit(frame, CoolLEDColors::Green) > 0));
+
+        let stars = Procedural::Starfield { seed: 5, stars: 6 }.frames::<16, 8>(30);
+        assert!(stars
+            .iter()
+            .all(|frame| lit(frame, CoolLEDColors::Black) >= 128 - 6));
+    }
+
+    #[test]
+    fn plasma_waves() {
+        let frames = Procedural::Plasma { period: 16 }.frames::<16, 8>(2);
+        assert_eq!(lit(&frames[0], CoolLEDColors::Black), 0);
+        assert!(
+            LIT.iter()
+                .filter(|color| lit(&frames[0], **color) > 0)
+                .count()
+                > 2
+        );
+        assert_ne!(frames[0], frames[1]);
+    }
+
+    #[test]
+    fn ball_bounces_on_the_sides() {
+        let frames = Procedural::BouncingBall {
+            size: 4,
+            color: CoolLEDColors::Red,
+        }
+        .frames::<16, 8>(30);
+        let left = |frame: &Canvas<16, 8>| {
+            (0..16).find(|x| (0..8).any(|y| frame.pixel(*x, y) == Some(CoolLEDColors::Red)))
+        };
+        assert_eq!(lit(&frames[0], CoolLEDColors::Red), 12);
+        assert_eq!(left(&frames[0]), Some(0));
+        assert_eq!(left(&frames[12]), Some(12));
+        assert_eq!(left(&frames[13]), Some(11));
+    }
+
+    #[test]
+    fn life_generations() {
+        //Vertical blinker
+        let mut cells = vec![false; 25];
+        for y in 1..4 {
+            cells[y * 5 + 2] = true;
+        }
+        let next = life_step(&cells, 5, 5);
+        let alive: Vec<_> = (0..25).filter(|idx| next[*idx]).collect();
+        assert_eq!(alive, [11, 12, 13]);
+        assert_eq!(life_step(&next, 5, 5), cells);
+    }
+
+    #[test]
+    fn spinner_turns() {
+        let frames = Procedural::Spinner {
+            tail: 2,
+            color: CoolLEDColors::Cyan,
+        }
+        .frames::<16, 8>(9);
+        assert_eq!(frames[0], frames[8]);
+        assert_ne!(frames[0], frames[1]);
+        assert_eq!(lit(&frames[3], CoolLEDColors::Cyan), 3);
+    }
+
+    #[test]
+    fn packets_of_a_large_animation() {
+        let mut builder = AnimationBuilder::<32, 16>::new();
+        Procedural::Plasma { period: 8 }
+            .push_frames(&mut builder, 255)
+            .unwrap();
+        let animation = builder.build().unwrap();
+        let mut writer = animation.writer();
+        let mut buffer = [0; 255];
+        let written: usize = (0..writer.get_packets_count())
+            .map(|idx| writer.generate_packet(idx, &mut buffer))
+            .sum();
+        assert!(written > animation.data.len());
+    }
+
+    #[test]
+    fn limits_are_checked_first() {
+        let mut builder = AnimationBuilder::<32, 16>::new();
+        assert_eq!(
+            Procedural::Plasma { period: 8 }.push_frames(&mut builder, 300),
+            Err(AnimationError::TooManyFrames { frames: 300 })
+        );
+        assert_eq!(builder.frames(), 0);
+    }
+}
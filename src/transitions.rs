@@ -0,0 +1,258 @@
+//! Animated transitions from a framebuffer to another.
+//!
+//! ```
+//! use coolled1248::animation::AnimationBuilder;
+//! use coolled1248::canvas::Canvas;
+//! use coolled1248::colors::CoolLEDColors;
+//! use coolled1248::transitions::{Direction, Transition};
+//!
+//! let mut first = Canvas::<32, 16>::new();
+//! first.fill(CoolLEDColors::Red);
+//! let mut second = Canvas::<32, 16>::new();
+//! second.fill(CoolLEDColors::Blue);
+//!
+//! let mut builder = AnimationBuilder::new().with_speed(50);
+//! builder.push_frame_repeated(&first, 20)?;
+//! Transition::Push(Direction::Up).push_frames(&mut builder, &first, &second, 16)?;
+//! builder.push_frame_repeated(&second, 20)?;
+//! Transition::Dissolve { seed: 7 }.push_frames(&mut builder, &second, &first, 10)?;
+//!
+//! let animation = builder.build()?;
+//! assert_eq!(animation.frames, 66);
+//! # Ok::<(), coolled1248::animation::AnimationError>(())
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::animation::{check_limits, AnimationBuilder, AnimationError};
+use crate::canvas::Canvas;
+use crate::colors::CoolLEDColors;
+use crate::util::XorShift;
+
+/// Direction in which a transition moves
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Transition between two framebuffers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    /// The new image slides over the old one
+    Slide(Direction),
+    /// The new image is uncovered over the old one, neither of them moves
+    Wipe(Direction),
+    /// The pixels change to the new image one by one, in a random order
+    /// given by `seed`
+    Dissolve { seed: u32 },
+    /// The new image pushes the old one out of the panel
+    Push(Direction),
+}
+
+impl Transition {
+    /// Push to `builder` the `frames` frames of the transition from `from` to
+    /// `to`, the last one being `to`. Fails without pushing a frame when the
+    /// animation would exceed the limits of the device.
+    pub fn push_frames<const W: usize, const H: usize>(
+        &self,
+        builder: &mut AnimationBuilder<W, H>,
+        from: &Canvas<W, H>,
+        to: &Canvas<W, H>,
+        frames: usize,
+    ) -> Result<(), AnimationError> {
+        check_limits(builder.frames().saturating_add(frames), Canvas::<W, H>::PLANE_SIZE)?;
+
+        let ranks = match self {
+            Transition::Dissolve { seed } => shuffled(W * H, *seed),
+            _ => Vec::new(),
+        };
+        for step in 1..=frames {
+            let mut frame = Canvas::new();
+            for (x, y) in (0..H).flat_map(|y| (0..W).map(move |x| (x, y))) {
+                let color = match *self {
+                    Transition::Dissolve { .. } => {
+                        let changed = ranks[y * W + x] < W * H * step / frames;
+                        color_at(if changed { to } else { from }, x, y)
+                    }
+                    Transition::Slide(direction)
+                    | Transition::Wipe(direction)
+                    | Transition::Push(direction) => {
+                        self.moved(direction, from, to, x, y, step, frames)
+                    }
+                };
+                frame.set_pixel(x as i32, y as i32, color);
+            }
+            builder.push_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    //Color of a pixel of a slide, wipe or push frame
+    #[allow(clippy::too_many_arguments)]
+    fn moved<const W: usize, const H: usize>(
+        &self,
+        direction: Direction,
+        from: &Canvas<W, H>,
+        to: &Canvas<W, H>,
+        x: usize,
+        y: usize,
+        step: usize,
+        frames: usize,
+    ) -> CoolLEDColors {
+        //Position along the motion, counted from where the new image enters
+        //backwards, and the pixel at such a position in the row or column
+        let (extent, position) = match direction {
+            Direction::Left => (W, x),
+            Direction::Right => (W, W - 1 - x),
+            Direction::Up => (H, y),
+            Direction::Down => (H, H - 1 - y),
+        };
+        let at = |canvas, position: usize| match direction {
+            Direction::Left => color_at(canvas, position, y),
+            Direction::Right => color_at(canvas, W - 1 - position, y),
+            Direction::Up => color_at(canvas, x, position),
+            Direction::Down => color_at(canvas, x, H - 1 - position),
+        };
+
+        let shift = extent * step / frames;
+        let edge = extent - shift;
+        match (self, position < edge) {
+            (Transition::Push(_), true) => at(from, position + shift),
+            (_, true) => at(from, position),
+            (Transition::Wipe(_), false) => at(to, position),
+            (_, false) => at(to, position - edge),
+        }
+    }
+}
+
+fn color_at<const W: usize, const H: usize>(
+    canvas: &Canvas<W, H>,
+    x: usize,
+    y: usize,
+) -> CoolLEDColors {
+    canvas
+        .pixel(x as i32, y as i32)
+        .unwrap_or(CoolLEDColors::Black)
+}
+
+//Permutation of 0..len shuffled with a xorshift generator
+fn shuffled(len: usize, seed: u32) -> Vec<usize> {
+    let mut random = XorShift::new(seed);
+    let mut values: Vec<usize> = (0..len).collect();
+    for idx in (1..len).rev() {
+        values.swap(idx, random.below(idx + 1));
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    //Columns 0 and 1 of `to` are blue and green
+    fn canvases() -> (Canvas<4, 2>, Canvas<4, 2>) {
+        let mut from = Canvas::new();
+        from.fill(CoolLEDColors::Red);
+        let mut to = Canvas::new();
+        to.fill_rect(0, 0, 1, 2, CoolLEDColors::Blue);
+        to.fill_rect(1, 0, 1, 2, CoolLEDColors::Green);
+        (from, to)
+    }
+
+    fn frames(transition: Transition, frames: usize) -> Vec<[CoolLEDColors; 4]> {
+        let (from, to) = canvases();
+        let mut builder = AnimationBuilder::new();
+        transition
+            .push_frames(&mut builder, &from, &to, frames)
+            .unwrap();
+        let preview = crate::preview::Preview::new(&builder.build().unwrap().writer(), 2);
+        (0..frames)
+            .map(|frame| core::array::from_fn(|x| preview.pixel(frame, x, 0)))
+            .collect()
+    }
+
+    #[test]
+    fn slide_wipe_and_push() {
+        use CoolLEDColors::{Black, Blue, Green, Red};
+        assert_eq!(
+            frames(Transition::Slide(Direction::Left), 2),
+            [[Red, Red, Blue, Green], [Blue, Green, Black, Black]]
+        );
+        assert_eq!(
+            frames(Transition::Wipe(Direction::Right), 2),
+            [[Blue, Green, Red, Red], [Blue, Green, Black, Black]]
+        );
+        assert_eq!(
+            frames(Transition::Push(Direction::Left), 4),
+            [
+                [Red, Red, Red, Blue],
+                [Red, Red, Blue, Green],
+                [Red, Blue, Green, Black],
+                [Blue, Green, Black, Black]
+            ]
+        );
+        assert_eq!(
+            frames(Transition::Push(Direction::Right), 2),
+            [[Black, Black, Red, Red], [Blue, Green, Black, Black]]
+        );
+    }
+
+    #[test]
+    fn vertical_push() {
+        let (mut from, to) = canvases();
+        from.fill_rect(0, 1, 4, 1, CoolLEDColors::Yellow);
+        let mut builder = AnimationBuilder::new();
+        Transition::Push(Direction::Up)
+            .push_frames(&mut builder, &from, &to, 2)
+            .unwrap();
+        let preview = crate::preview::Preview::new(&builder.build().unwrap().writer(), 2);
+        assert_eq!(preview.pixel(0, 0, 0), CoolLEDColors::Yellow);
+        assert_eq!(preview.pixel(0, 0, 1), CoolLEDColors::Blue);
+        assert_eq!(preview.pixel(1, 1, 1), CoolLEDColors::Green);
+    }
+
+    #[test]
+    fn dissolve_changes_pixels_randomly() {
+        let changed = |frame: &[CoolLEDColors; 4]| {
+            frame
+                .iter()
+                .filter(|color| **color != CoolLEDColors::Red)
+                .count()
+        };
+        let dissolve = frames(Transition::Dissolve { seed: 3 }, 4);
+        assert!(dissolve
+            .windows(2)
+            .all(|frames| changed(&frames[0]) <= changed(&frames[1])));
+        assert_eq!(
+            dissolve[3],
+            [
+                CoolLEDColors::Blue,
+                CoolLEDColors::Green,
+                CoolLEDColors::Black,
+                CoolLEDColors::Black
+            ]
+        );
+        assert_eq!(dissolve, frames(Transition::Dissolve { seed: 3 }, 4));
+
+        let mut ranks = shuffled(8, 3);
+        assert_ne!(ranks, shuffled(8, 4));
+        ranks.sort();
+        assert_eq!(ranks, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn frame_limit_is_checked_first() {
+        let (from, to) = canvases();
+        let mut builder = AnimationBuilder::new();
+        builder.push_frame_repeated(&from, 250).unwrap();
+        assert_eq!(
+            Transition::Wipe(Direction::Up).push_frames(&mut builder, &from, &to, 10),
+            Err(AnimationError::TooManyFrames { frames: 260 })
+        );
+        assert_eq!(builder.frames(), 250);
+    }
+}
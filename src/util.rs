@@ -1,13 +1,16 @@
 /// Escape bytes 0x01, 0x02 and 0x3 wit an aditional byte. (i.e 0x01 turns into 0x02 0x05)
 pub fn escape_byets_in_place(out: &mut [u8], current_bytes_wrote: usize) -> usize {
     let mut last_bytes_wrote = current_bytes_wrote;
-    for idx in 0..out.len() {
+    //Only the written bytes, the rest of `out` may hold a previous packet
+    let mut idx = 0;
+    while idx < last_bytes_wrote {
         if out[idx] > 0 && out[idx] < 4 {
             out[idx..].copy_within(0..last_bytes_wrote - idx, 1);
             out[idx] = 0x02;
             out[idx + 1] ^= 0x4;
             last_bytes_wrote += 1;
         }
+        idx += 1;
     }
     last_bytes_wrote
 }
@@ -24,4 +27,44 @@ pub fn escpae_bytes<F: FnMut(u8)>(mut func: F, data: u8) {
 
 pub fn calculate_checksum(data: &[u8]) -> u8 {
     data.iter().fold(0, |acc, e| acc ^ e)
-}
\ No newline at end of file
+}
+
+/// Xorshift pseudo random generator, the same seed gives the same values
+#[cfg(feature = "alloc")]
+pub struct XorShift(u32);
+
+#[cfg(feature = "alloc")]
+impl XorShift {
+    pub fn new(seed: u32) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// Value in `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.next_u32() as usize % bound
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape_only_the_written_bytes() {
+        //Two bytes written, the rest is left from a previous packet
+        let mut out = [0x10, 0x01, 0xff, 0x02, 0x03, 0x01];
+        assert_eq!(escape_byets_in_place(&mut out, 2), 3);
+        assert_eq!(out, [0x10, 0x02, 0x05, 0x02, 0x03, 0x01]);
+
+        let mut out = [0x03, 0x20, 0x01, 0x00, 0x00, 0x02];
+        assert_eq!(escape_byets_in_place(&mut out, 3), 5);
+        assert_eq!(out, [0x02, 0x07, 0x20, 0x02, 0x05, 0x02]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Data widgets drawn on a [`Canvas`], next to text, and sent as an image.
+//!
+//! ```
+//! use coolled1248::canvas::Canvas;
+//! use coolled1248::colors::CoolLEDColors;
+//! use coolled1248::coolled::{CoolLEDWriter, PayloadType};
+//! use coolled1248::font::Font;
+//! use coolled1248::small_font::{SmallFont, STYLE};
+//! use coolled1248::widgets::{ProgressBar, Sparkline};
+//!
+//! let font = Font::new(SmallFont).with_style(STYLE);
+//! let mut canvas = Canvas::<48, 16>::new();
+//! ProgressBar {
+//!     percent: 42,
+//!     width: 48,
+//!     height: 7,
+//!     ..Default::default()
+//! }
+//! .draw(&mut canvas, &font, 0, 0);
+//! Sparkline {
+//!     values: &[3.0, 5.0, 4.0, 8.0, 6.0, 9.0],
+//!     height: 8,
+//!     ..Default::default()
+//! }
+//! .draw(&mut canvas, 0, 8);
+//!
+//! let mut payload = [0; Canvas::<48, 16>::SIZE];
+//! canvas.write_planes(&mut payload);
+//! let writer = CoolLEDWriter::new(PayloadType::Image(&payload));
+//! ```
+
+use crate::canvas::Canvas;
+use crate::colors::CoolLEDColors;
+use crate::font::{Font, GlyphProvider};
+
+/// Horizontal progress bar followed by its percentage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressBar {
+    /// Progress from 0 to 100, higher values are a full bar
+    pub percent: u8,
+    /// Columns of the bar and its label
+    pub width: usize,
+    pub height: usize,
+    pub color: CoolLEDColors,
+    /// Color of the part of the bar left to fill
+    pub track: CoolLEDColors,
+    /// Color of the percentage drawn right of the bar from its top row, no
+    /// percentage when `None`
+    pub label: Option<CoolLEDColors>,
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self {
+            percent: 0,
+            width: 32,
+            height: 4,
+            color: CoolLEDColors::Green,
+            track: CoolLEDColors::Blue,
+            label: Some(CoolLEDColors::White),
+        }
+    }
+}
+
+impl ProgressBar {
+    /// Draw the bar with its top left corner at `(x, y)`. Room is kept for a
+    /// `100%` label so the bar does not change size with the percentage.
+    pub fn draw<const W: usize, const H: usize, G: GlyphProvider>(
+        &self,
+        canvas: &mut Canvas<W, H>,
+        font: &Font<'_, G>,
+        x: i32,
+        y: i32,
+    ) {
+        let percent = self.percent.min(100);
+        let mut bar_width = self.width;
+
+        if let Some(color) = self.label {
+            let mut buffer = [b'%'; 4];
+            let digits = match percent {
+                100 => 3,
+                10..=99 => 2,
+                _ => 1,
+            };
+            let mut rest = percent;
+            for digit in buffer[..digits].iter_mut().rev() {
+                *digit = b'0' + rest % 10;
+                rest /= 10;
+            }
+            let label = core::str::from_utf8(&buffer[..=digits]).unwrap_or_default();
+
+            bar_width = bar_width.saturating_sub(font.measure("100%") + 1);
+            let label_x = x + self.width.saturating_sub(font.measure(label)) as i32;
+            canvas.draw_text(font, label, label_x, y, &[color]);
+        }
+
+        let filled = bar_width * percent as usize / 100;
+        canvas.fill_rect(x, y, filled, self.height, self.color);
+        canvas.fill_rect(
+            x + filled as i32,
+            y,
+            bar_width - filled,
+            self.height,
+            self.track,
+        );
+    }
+}
+
+/// Line of the last values of a series, scaled from their minimum on the
+/// bottom row to their maximum on the top one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sparkline<'v> {
+    pub values: &'v [f32],
+    /// Columns of the line, a value per column
+    pub width: usize,
+    pub height: usize,
+    pub color: CoolLEDColors,
+}
+
+impl Default for Sparkline<'_> {
+    fn default() -> Self {
+        Self {
+            values: &[],
+            width: 32,
+            height: 16,
+            color: CoolLEDColors::Cyan,
+        }
+    }
+}
+
+impl Sparkline<'_> {
+    /// Draw the line with its top left corner at `(x, y)`. Consecutive
+    /// values are joined, a flat series is drawn on the bottom row.
+    pub fn draw<const W: usize, const H: usize>(&self, canvas: &mut Canvas<W, H>, x: i32, y: i32) {
+        let values = &self.values[self.values.len().saturating_sub(self.width)..];
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let bottom = self.height as i32 - 1;
+        let row = |value: f32| {
+            if max > min {
+                bottom - ((value - min) / (max - min) * bottom as f32 + 0.5) as i32
+            } else {
+                bottom
+            }
+        };
+
+        let mut previous = None;
+        for (column, value) in values.iter().enumerate() {
+            let current = row(*value);
+            let (top, end) = match previous {
+                Some(previous) if previous < current => (previous + 1, current),
+                Some(previous) if previous > current => (current, previous - 1),
+                _ => (current, current),
+            };
+            let column = x + column as i32;
+            canvas.fill_rect(column, y + top, 1, (end - top + 1) as usize, self.color);
+            previous = Some(current);
+        }
+    }
+}
+
+/// Vertical gauge colored by the range its value is in: below `warning`,
+/// below `critical` or above
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gauge {
+    pub value: f32,
+    /// Value of a full gauge, it starts from 0
+    pub max: f32,
+    pub warning: f32,
+    pub critical: f32,
+    pub width: usize,
+    pub height: usize,
+    /// Colors of the normal, warning and critical values
+    pub colors: [CoolLEDColors; 3],
+    /// Color of the empty part of the gauge
+    pub track: CoolLEDColors,
+}
+
+impl Default for Gauge {
+    fn default() -> Self {
+        Self {
+            value: 0.0,
+            max: 100.0,
+            warning: 70.0,
+            critical: 90.0,
+            width: 4,
+            height: 16,
+            colors: [
+                CoolLEDColors::Green,
+                CoolLEDColors::Yellow,
+                CoolLEDColors::Red,
+            ],
+            track: CoolLEDColors::Black,
+        }
+    }
+}
+
+impl Gauge {
+    /// Color of the gauge for its value
+    pub fn color(&self) -> CoolLEDColors {
+        if self.value >= self.critical {
+            self.colors[2]
+        } else if self.value >= self.warning {
+            self.colors[1]
+        } else {
+            self.colors[0]
+        }
+    }
+
+    /// Draw the gauge with its top left corner at `(x, y)`, filled from the
+    /// bottom
+    pub fn draw<const W: usize, const H: usize>(&self, canvas: &mut Canvas<W, H>, x: i32, y: i32) {
+        let ratio = (self.value / self.max).clamp(0.0, 1.0);
+        let filled = (ratio * self.height as f32 + 0.5) as usize;
+        let empty = self.height - filled;
+        canvas.fill_rect(x, y, self.width, empty, self.track);
+        canvas.fill_rect(x, y + empty as i32, self.width, filled, self.color());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::small_font::{SmallFont, STYLE};
+
+    fn column(canvas: &Canvas<32, 16>, x: i32) -> [CoolLEDColors; 16] {
+        core::array::from_fn(|y| canvas.pixel(x, y as i32).unwrap())
+    }
+
+    #[test]
+    fn progress_bar_with_label() {
+        let font = Font::new(SmallFont).with_style(STYLE);
+        let mut canvas = Canvas::<32, 16>::new();
+        let bar = ProgressBar {
+            percent: 50,
+            height: 2,
+            ..Default::default()
+        };
+        bar.draw(&mut canvas, &font, 0, 0);
+        //"100%" takes 23 columns, the bar the first 8
+        assert_eq!(canvas.pixel(3, 1), Some(CoolLEDColors::Green));
+        assert_eq!(canvas.pixel(4, 1), Some(CoolLEDColors::Blue));
+        assert_eq!(canvas.pixel(7, 0), Some(CoolLEDColors::Blue));
+        assert_eq!(canvas.pixel(8, 0), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(0, 2), Some(CoolLEDColors::Black));
+
+        //"50%" right aligned
+        let mut label = Canvas::<32, 16>::new();
+        label.draw_text(&font, "50%", 15, 0, &[CoolLEDColors::White]);
+        for x in 9..32 {
+            assert_eq!(column(&canvas, x), column(&label, x));
+        }
+
+        let mut full = Canvas::<32, 16>::new();
+        ProgressBar {
+            percent: 200,
+            label: None,
+            ..bar
+        }
+        .draw(&mut full, &font, 0, 0);
+        assert_eq!(full.pixel(31, 1), Some(CoolLEDColors::Green));
+    }
+
+    #[test]
+    fn sparkline_is_scaled() {
+        let mut canvas = Canvas::<32, 16>::new();
+        let values = [7.0, 0.0, 10.0, 20.0, 10.0];
+        Sparkline {
+            values: &values,
+            width: 4,
+            ..Default::default()
+        }
+        .draw(&mut canvas, 1, 0);
+
+        let lit = |x| {
+            let column = column(&canvas, x);
+            let mut rows = (0..16).filter(move |y| column[*y] == CoolLEDColors::Cyan);
+            (rows.next(), rows.next_back())
+        };
+        assert_eq!(lit(1), (Some(15), None));
+        //Rows are joined to the previous value
+        assert_eq!(lit(2), (Some(7), Some(14)));
+        assert_eq!(lit(3), (Some(0), Some(6)));
+        assert_eq!(lit(4), (Some(1), Some(7)));
+        assert_eq!(lit(5), (None, None));
+
+        let mut flat = Canvas::<32, 16>::new();
+        Sparkline {
+            values: &[3.0, 3.0],
+            ..Default::default()
+        }
+        .draw(&mut flat, 0, 0);
+        assert_eq!(flat.pixel(1, 15), Some(CoolLEDColors::Cyan));
+    }
+
+    #[test]
+    fn gauge_thresholds() {
+        let mut gauge = Gauge {
+            value: 50.0,
+            width: 2,
+            ..Default::default()
+        };
+        assert_eq!(gauge.color(), CoolLEDColors::Green);
+        gauge.value = 75.0;
+        assert_eq!(gauge.color(), CoolLEDColors::Yellow);
+        gauge.value = 90.0;
+        assert_eq!(gauge.color(), CoolLEDColors::Red);
+
+        let mut canvas = Canvas::<32, 16>::new();
+        gauge.draw(&mut canvas, 30, 0);
+        //14 of the 16 rows filled
+        assert_eq!(canvas.pixel(31, 1), Some(CoolLEDColors::Black));
+        assert_eq!(canvas.pixel(31, 2), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(30, 15), Some(CoolLEDColors::Red));
+        assert_eq!(canvas.pixel(29, 15), Some(CoolLEDColors::Black));
+    }
+}
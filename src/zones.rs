@@ -0,0 +1,293 @@
+//! Panels split into rectangular zones, each with its own content.
+//!
+//! The [`Compositor`] merges the zones into one animation. Every zone loops
+//! over its frames, so the animation lasts the least common multiple of the
+//! zone cycles, cut to the limits of the device.
+//!
+//! ```
+//! use coolled1248::canvas::Canvas;
+//! use coolled1248::colors::CoolLEDColors;
+//! use coolled1248::coolled::{CoolLEDWriter, PayloadType};
+//! use coolled1248::zones::{Compositor, Zone, ZoneContent};
+//!
+//! let mut icon = Canvas::<16, 16>::new();
+//! icon.fill_rect(4, 4, 8, 8, CoolLEDColors::Yellow);
+//! let mut planes = [0; Canvas::<16, 16>::SIZE];
+//! icon.write_planes(&mut planes);
+//!
+//! let colors = [CoolLEDColors::Cyan];
+//! let text = CoolLEDWriter::new(PayloadType::Text("Build passed", &colors));
+//!
+//! let animation = Compositor::<48, 16>::new()
+//!     .with_speed(60)
+//!     .with_zone(Zone::new(0, 0, 16, 16, ZoneContent::Image(&planes)))
+//!     .with_zone(Zone::new(16, 0, 32, 16, ZoneContent::Text { writer: &text, scroll: true }))
+//!     .build()?;
+//! # Ok::<(), coolled1248::animation::AnimationError>(())
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::animation::{Animation, AnimationBuilder, AnimationError};
+use crate::canvas::Canvas;
+use crate::coolled::{
+    CoolLEDWriter, PayloadType, DEFAULT_ANIMATION_SPEED, MAX_ANIMATION_FRAMES, MAX_ANIMATION_SIZE,
+};
+use crate::font::{GlyphProvider, UnicodeFont};
+use crate::packets::EffectsMode;
+use crate::preview::Preview;
+
+/// Content of a zone, drawn from its top left corner and cropped to it
+pub enum ZoneContent<'a, G = UnicodeFont> {
+    /// Text of a text writer. When scrolled it enters on the right of the
+    /// zone and leaves on the left, a column a frame.
+    Text {
+        writer: &'a CoolLEDWriter<'a, G>,
+        scroll: bool,
+    },
+    /// Planes of an image as tall as the zone
+    Image(&'a [u8]),
+    /// Animation as tall as the zone, its speed is not used
+    Animation(&'a Animation),
+}
+
+/// Rectangle of the panel and its content
+pub struct Zone<'a, G = UnicodeFont> {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub content: ZoneContent<'a, G>,
+}
+
+impl<'a, G: GlyphProvider> Zone<'a, G> {
+    pub fn new(
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        content: ZoneContent<'a, G>,
+    ) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            content,
+        }
+    }
+
+    //Frames the zone loops over
+    fn frames(&self, speed: u16) -> Preview {
+        match self.content {
+            ZoneContent::Text { writer, scroll } => {
+                let text = Preview::new(writer, self.height);
+                if scroll {
+                    text.with_effect(EffectsMode::Left, self.width, speed)
+                } else {
+                    text
+                }
+            }
+            ZoneContent::Image(planes) => {
+                Preview::new(&CoolLEDWriter::new(PayloadType::Image(planes)), self.height)
+            }
+            ZoneContent::Animation(animation) => Preview::new(&animation.writer(), self.height),
+        }
+    }
+}
+
+/// Merges zones of a `W` by `H` panel into an animation, the zones added
+/// last being drawn over the others
+pub struct Compositor<'a, const W: usize, const H: usize, G = UnicodeFont> {
+    zones: Vec<Zone<'a, G>>,
+    speed: u16,
+}
+
+impl<const W: usize, const H: usize, G: GlyphProvider> Default for Compositor<'_, W, H, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const W: usize, const H: usize, G: GlyphProvider> Compositor<'a, W, H, G> {
+    pub fn new() -> Self {
+        Self {
+            zones: Vec::new(),
+            speed: DEFAULT_ANIMATION_SPEED,
+        }
+    }
+
+    /// Set the delay between the frames in milliseconds, it must not be 0
+    pub fn with_speed(mut self, speed: u16) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_zone(mut self, zone: Zone<'a, G>) -> Self {
+        self.zones.push(zone);
+        self
+    }
+
+    /// Frames of the animation: the least common multiple of the zone
+    /// cycles, cut to the most frames the device plays
+    pub fn frames(&self) -> usize {
+        self.cycle(&self.previews())
+    }
+
+    pub fn build(&self) -> Result<Animation, AnimationError> {
+        let previews = self.previews();
+        let mut builder = AnimationBuilder::<W, H>::new().with_speed(self.speed);
+        for frame in 0..self.cycle(&previews) {
+            let mut canvas = Canvas::new();
+            for (zone, preview) in self.zones.iter().zip(&previews) {
+                let shown = frame % preview.frames.len();
+                for y in 0..zone.height.min(preview.height) {
+                    for x in 0..zone.width.min(preview.width) {
+                        let color = preview.pixel(shown, x, y);
+                        canvas.set_pixel((zone.x + x) as i32, (zone.y + y) as i32, color);
+                    }
+                }
+            }
+            builder.push_frame(&canvas)?;
+        }
+        builder.build()
+    }
+
+    fn previews(&self) -> Vec<Preview> {
+        self.zones
+            .iter()
+            .map(|zone| zone.frames(self.speed))
+            .collect()
+    }
+
+    fn cycle(&self, previews: &[Preview]) -> usize {
+        let limit = MAX_ANIMATION_FRAMES.min(MAX_ANIMATION_SIZE / Canvas::<W, H>::SIZE);
+        previews
+            .iter()
+            .map(|preview| preview.frames.len())
+            .fold(1, |cycle, frames| {
+                (cycle / gcd(cycle, frames))
+                    .saturating_mul(frames)
+                    .min(limit)
+            })
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::colors::CoolLEDColors;
+
+    //Animation of 2x8 frames, each filled with a color
+    fn animation(colors: &[CoolLEDColors]) -> Animation {
+        let mut builder = AnimationBuilder::<2, 8>::new();
+        for color in colors {
+            let mut frame = Canvas::new();
+            frame.fill(*color);
+            builder.push_frame(&frame).unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn zones_loop_over_their_frames() {
+        use CoolLEDColors::{Blue, Green, Red, White, Yellow};
+        let two = animation(&[Red, Green]);
+        let three = animation(&[Blue, White, Yellow]);
+        let compositor = Compositor::<4, 8>::new()
+            .with_zone(Zone::new(0, 0, 2, 8, ZoneContent::Animation(&two)))
+            .with_zone(Zone::new(2, 0, 2, 8, ZoneContent::Animation(&three)));
+        assert_eq!(compositor.frames(), 6);
+
+        let result = compositor.build().unwrap();
+        let preview = Preview::new(&result.writer(), 8);
+        let frames: Vec<_> = (0..6)
+            .map(|frame| (preview.pixel(frame, 1, 7), preview.pixel(frame, 2, 0)))
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                (Red, Blue),
+                (Green, White),
+                (Red, Yellow),
+                (Green, Blue),
+                (Red, White),
+                (Green, Yellow)
+            ]
+        );
+    }
+
+    #[test]
+    fn zones_are_cropped() {
+        let mut image = Canvas::<4, 8>::new();
+        image.fill(CoolLEDColors::Pink);
+        let mut planes = [0; Canvas::<4, 8>::SIZE];
+        image.write_planes(&mut planes);
+
+        let result = Compositor::<8, 8>::new()
+            .with_zone(Zone::new(1, 2, 2, 3, ZoneContent::Image(&planes)))
+            .build()
+            .unwrap();
+        assert_eq!(result.frames, 1);
+        let preview = Preview::new(&result.writer(), 8);
+        let lit: Vec<_> = (0..64)
+            .filter(|idx| preview.pixel(0, idx % 8, idx / 8) == CoolLEDColors::Pink)
+            .collect();
+        assert_eq!(lit, [17, 18, 25, 26, 33, 34]);
+    }
+
+    #[test]
+    fn scrolling_text_and_limits() {
+        let colors = [CoolLEDColors::Red];
+        let text = CoolLEDWriter::new(PayloadType::Text("A", &colors));
+        let strip = Preview::new(&text, 16).width;
+
+        let still = Compositor::<32, 16>::new().with_zone(Zone::new(
+            0,
+            0,
+            32,
+            16,
+            ZoneContent::Text {
+                writer: &text,
+                scroll: false,
+            },
+        ));
+        assert_eq!(still.frames(), 1);
+        let scrolled = Compositor::<32, 16>::new().with_zone(Zone::new(
+            8,
+            0,
+            24,
+            16,
+            ZoneContent::Text {
+                writer: &text,
+                scroll: true,
+            },
+        ));
+        assert_eq!(scrolled.frames(), strip + 24);
+
+        let long = animation(&[CoolLEDColors::Red; 200]);
+        let short = animation(&[CoolLEDColors::Blue; 3]);
+        let capped = Compositor::<4, 8>::new()
+            .with_zone(Zone::new(0, 0, 2, 8, ZoneContent::Animation(&long)))
+            .with_zone(Zone::new(2, 0, 2, 8, ZoneContent::Animation(&short)));
+        assert_eq!(capped.frames(), MAX_ANIMATION_FRAMES);
+        assert_eq!(capped.build().unwrap().frames, MAX_ANIMATION_FRAMES);
+
+        let wide = Compositor::<256, 16>::new().with_zone(Zone::new(
+            0,
+            0,
+            2,
+            8,
+            ZoneContent::Animation(&long),
+        ));
+        assert_eq!(wide.frames(), MAX_ANIMATION_SIZE / Canvas::<256, 16>::SIZE);
+    }
+}